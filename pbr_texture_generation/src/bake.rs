@@ -0,0 +1,173 @@
+use nalgebra::{Vector2, Vector3, vector};
+
+/// Upper bound on the number of steps marched along each direction around a pixel.
+const MAX_STEPS: u32 = 16;
+
+pub struct AmbientOcclusionParameters {
+    /// Distance up to which the surroundings occlude a pixel, in world units
+    pub radius: f32,
+    /// Number of directions the horizon is searched along
+    pub samples: u32,
+}
+
+impl Default for AmbientOcclusionParameters {
+    fn default() -> Self {
+        Self {
+            radius: 0.03,
+            samples: 16,
+        }
+    }
+}
+
+/// Heights and normals of the pixels of a map, heights in world units, the ground lying at the
+/// lowest height of the yarns. Lookups outside of the map wrap around it when it tiles and clamp
+/// to its edges otherwise.
+pub struct SurfaceField {
+    size: Vector2<u32>,
+    /// Extent of a pixel in world units
+    pixel_size: Vector2<f32>,
+    wrap: bool,
+    /// Rows from top to bottom, as in the maps
+    heights: Vec<f32>,
+    normals: Vec<Vector3<f32>>,
+}
+
+impl SurfaceField {
+    pub fn new(
+        size: Vector2<u32>,
+        extent: Vector2<f32>,
+        wrap: bool,
+        mut heights: Vec<f32>,
+        normals: Vec<Vector3<f32>>,
+    ) -> Self {
+        let ground = heights
+            .iter()
+            .copied()
+            .filter(|h| h.is_finite())
+            .reduce(f32::min)
+            .unwrap_or(0.);
+        heights
+            .iter_mut()
+            .filter(|h| !h.is_finite())
+            .for_each(|h| *h = ground);
+        Self {
+            size,
+            pixel_size: extent.component_div(&size.map(|x| x as f32)),
+            wrap,
+            heights,
+            normals,
+        }
+    }
+
+    fn index(&self, x: i64, y: i64) -> usize {
+        let (w, h) = (self.size.x as i64, self.size.y as i64);
+        let (x, y) = if self.wrap {
+            (x.rem_euclid(w), y.rem_euclid(h))
+        } else {
+            (x.clamp(0, w - 1), y.clamp(0, h - 1))
+        };
+        (y * w + x) as usize
+    }
+
+    pub fn height(&self, x: i64, y: i64) -> f32 {
+        self.heights[self.index(x, y)]
+    }
+
+    pub fn normal(&self, x: i64, y: i64) -> Vector3<f32> {
+        self.normals[self.index(x, y)]
+    }
+
+    /// Bilinear interpolation of the heights at a position in pixels.
+    fn interpolate(&self, p: Vector2<f32>) -> f32 {
+        let (x, y) = (p.x.floor(), p.y.floor());
+        let (fx, fy) = (p.x - x, p.y - y);
+        let (x, y) = (x as i64, y as i64);
+        let top = self.height(x, y) * (1. - fx) + self.height(x + 1, y) * fx;
+        let bottom = self.height(x, y + 1) * (1. - fx) + self.height(x + 1, y + 1) * fx;
+        top * (1. - fy) + bottom * fy
+    }
+
+    /// Surroundings of pixel `(x, y)` up to `radius`, marched along evenly spaced directions, as
+    /// the distance and the height relative to the pixel of every step.
+    fn around(
+        &self,
+        x: i64,
+        y: i64,
+        parameters: &AmbientOcclusionParameters,
+    ) -> impl Iterator<Item = impl Iterator<Item = (f32, f32)>> {
+        let origin = self.height(x, y);
+        let center = vector![x as f32, y as f32];
+        let radius = parameters.radius;
+        let steps = ((radius / self.pixel_size.min()).ceil() as u32).clamp(1, MAX_STEPS);
+        let directions = parameters.samples.max(1);
+
+        (0..directions).map(move |i| {
+            // Offset by half a step so that the axes, along which yarns run, are not favoured.
+            let angle = (i as f32 + 0.5) / directions as f32 * std::f32::consts::TAU;
+            let direction = vector![angle.cos(), angle.sin()];
+            (1..=steps).map(move |s| {
+                let distance = s as f32 / steps as f32 * radius;
+                let offset = (direction * distance).component_div(&self.pixel_size);
+                (distance, self.interpolate(center + offset) - origin)
+            })
+        })
+    }
+
+    /// Horizon based ambient occlusion of pixel `(x, y)`, from 1 when nothing rises above it to
+    /// 0 when it lies at the bottom of a deep well.
+    pub fn ambient_occlusion(
+        &self,
+        x: i64,
+        y: i64,
+        parameters: &AmbientOcclusionParameters,
+    ) -> f32 {
+        let directions = parameters.samples.max(1);
+        let visibility = self
+            .around(x, y, parameters)
+            .map(|steps| {
+                let max_sin = steps
+                    .map(|(distance, dz)| dz / (dz * dz + distance * distance).sqrt())
+                    .fold(0., f32::max);
+                1. - max_sin
+            })
+            .sum::<f32>();
+        visibility / directions as f32
+    }
+
+    /// Depth of pixel `(x, y)` below the mean height of its surroundings, negative when it rises
+    /// above them.
+    pub fn cavity(&self, x: i64, y: i64, parameters: &AmbientOcclusionParameters) -> f32 {
+        let (sum, count) = self
+            .around(x, y, parameters)
+            .flatten()
+            .fold((0., 0), |(sum, count), (_, dz)| (sum + dz, count + 1));
+        sum / count as f32
+    }
+
+    /// Normal of the height field at pixel `(x, y)` from central differences.
+    pub fn height_normal(&self, x: i64, y: i64) -> Vector3<f32> {
+        // Rows go down while the world goes up.
+        let dx = (self.height(x + 1, y) - self.height(x - 1, y)) / (2. * self.pixel_size.x);
+        let dy = (self.height(x, y - 1) - self.height(x, y + 1)) / (2. * self.pixel_size.y);
+        Vector3::new(-dx, -dy, 1.).normalize()
+    }
+
+    /// Divergence of the normals at pixel `(x, y)`, in inverse world units, positive where the
+    /// surface is convex and negative where it is concave.
+    pub fn curvature(&self, x: i64, y: i64) -> f32 {
+        // Rows go down while the world goes up.
+        let dx = (self.normal(x + 1, y).x - self.normal(x - 1, y).x) / (2. * self.pixel_size.x);
+        let dy = (self.normal(x, y - 1).y - self.normal(x, y + 1).y) / (2. * self.pixel_size.y);
+        dx + dy
+    }
+}
+
+/// Value below which a fraction `q` of the finite values lies.
+pub fn percentile(values: impl Iterator<Item = f32>, q: f32) -> f32 {
+    let mut values: Vec<f32> = values.filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return 0.;
+    }
+    let i = ((values.len() - 1) as f32 * q).round() as usize;
+    *values.select_nth_unstable_by(i, f32::total_cmp).1
+}
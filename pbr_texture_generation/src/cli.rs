@@ -0,0 +1,182 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use nalgebra::Vector2;
+
+use pbr_texture_generation::{
+    draft::WeavePreset,
+    knit::KnitStitch,
+    output::{Export, MapKind, NormalConvention, NormalSource, OutputFormat},
+    profile::CrossSection,
+    sampling::{MAX_SAMPLES, SamplePattern},
+    spline::Curve,
+};
+
+#[derive(Parser)]
+#[command(version, about = "Procedural PBR texture generator for fabrics")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Generate the PBR maps of a pattern
+    Generate(GenerateArgs),
+    /// List the available patterns, weave presets and stitches
+    ListPatterns,
+    /// Print statistics about the wires of a pattern without rendering it
+    Inspect(InspectArgs),
+}
+
+#[derive(Args)]
+pub struct PatternArgs {
+    /// Pattern used to build the world [default: tissage]
+    #[arg(short, long, value_enum, conflicts_with = "scene")]
+    pub pattern: Option<Pattern>,
+
+    /// Scene file describing the weave, its materials and its outputs
+    #[arg(long)]
+    pub scene: Option<PathBuf>,
+
+    /// Weave structure of the tissage pattern, replacing the one of the scene [default: plain]
+    #[arg(short, long, value_enum)]
+    pub weave: Option<WeavePreset>,
+
+    /// Stitch of the knit pattern, replacing the one of the scene [default: jersey]
+    #[arg(long, value_enum)]
+    pub stitch: Option<KnitStitch>,
+
+    /// Seed of the noise used to perturb the wires [default: 1234]
+    #[arg(short, long)]
+    pub seed: Option<u32>,
+
+    /// Interpolation of the nodes of the yarns, replacing the one of the scene [default: polyline]
+    #[arg(long, value_enum)]
+    pub curve: Option<Curve>,
+
+    /// Cross-section of the yarns, replacing the one of the scene [default: circle]
+    #[arg(long, value_enum)]
+    pub profile: Option<CrossSection>,
+
+    /// Height over width of the cross-section of the yarns, replacing the one of the scene
+    /// [default: 1]
+    #[arg(long, value_parser = parse_positive)]
+    pub aspect: Option<f32>,
+}
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    #[command(flatten)]
+    pub pattern: PatternArgs,
+
+    /// Size of the maps in pixels, either `N` or `WIDTHxHEIGHT` [default: 1024]
+    #[arg(short, long, value_parser = parse_resolution)]
+    pub resolution: Option<Vector2<u32>>,
+
+    /// Extent of the world covered by the maps, either `E` or `XxY` [default: 1]
+    #[arg(short, long, value_parser = parse_extent)]
+    pub extent: Option<Vector2<f32>>,
+
+    /// Directory the maps are written to [default: .]
+    #[arg(short, long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Prefix prepended to every file name
+    #[arg(long)]
+    pub prefix: Option<String>,
+
+    /// Maps to emit [default: the PBR maps, without the utility ones]
+    #[arg(short, long, value_enum, value_delimiter = ',')]
+    pub maps: Option<Vec<MapKind>>,
+
+    /// File format of the maps, ids are always written as 8 bits PNG [default: png8]
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Orientation of the green channel of the normal and tangent maps [default: opengl]
+    #[arg(long, value_enum)]
+    pub normal_convention: Option<NormalConvention>,
+
+    /// What the normal map is computed from [default: geometry]
+    #[arg(long, value_enum)]
+    pub normal_source: Option<NormalSource>,
+
+    /// Samples per pixel along each axis, N x N in total [default: 1]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=MAX_SAMPLES as i64))]
+    pub samples: Option<u32>,
+
+    /// Placement of the samples within a pixel [default: regular]
+    #[arg(long, value_enum)]
+    pub sample_pattern: Option<SamplePattern>,
+
+    /// Distance up to which the surroundings occlude a pixel, in world units [default: 0.03]
+    #[arg(long, value_parser = parse_positive)]
+    pub ao_radius: Option<f32>,
+
+    /// Number of directions searched for the ambient occlusion horizon [default: 16]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub ao_samples: Option<u32>,
+
+    /// Assets bundling the maps for a renderer, written next to them
+    #[arg(short = 'x', long, value_enum, value_delimiter = ',')]
+    pub export: Option<Vec<Export>>,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    #[command(flatten)]
+    pub pattern: PatternArgs,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Pattern {
+    /// Woven fabric of perturbed threads following a weave draft
+    Tissage,
+    /// Weft knitted fabric made of interlocking loops
+    Knit,
+    /// A single bent strand, useful to debug the profiles
+    SingleStrand,
+}
+
+fn parse_pair<T: std::str::FromStr + Copy>(s: &str) -> Result<(T, T), String> {
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<T>()
+            .map_err(|_| format!("`{v}` is not a valid number"))
+    };
+    match s.split_once(['x', 'X']) {
+        Some((a, b)) => Ok((parse(a)?, parse(b)?)),
+        None => {
+            let v = parse(s)?;
+            Ok((v, v))
+        }
+    }
+}
+
+fn parse_resolution(s: &str) -> Result<Vector2<u32>, String> {
+    let (x, y) = parse_pair::<u32>(s)?;
+    if x == 0 || y == 0 {
+        return Err("resolution must be at least 1x1".to_string());
+    }
+    Ok(Vector2::new(x, y))
+}
+
+fn parse_extent(s: &str) -> Result<Vector2<f32>, String> {
+    let (x, y) = parse_pair::<f32>(s)?;
+    if !(x.is_finite() && y.is_finite() && x > 0. && y > 0.) {
+        return Err("extent must be finite and strictly positive".to_string());
+    }
+    Ok(Vector2::new(x, y))
+}
+
+fn parse_positive(s: &str) -> Result<f32, String> {
+    let v = s
+        .trim()
+        .parse::<f32>()
+        .map_err(|_| format!("`{s}` is not a valid number"))?;
+    if !(v.is_finite() && v > 0.) {
+        return Err("must be finite and strictly positive".to_string());
+    }
+    Ok(v)
+}
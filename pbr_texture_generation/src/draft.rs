@@ -0,0 +1,383 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+/// Interlacement matrix of a woven structure, repeated periodically over the fabric.
+///
+/// `ends` are the warp threads (columns) and `picks` the weft threads (rows). A raised cell means
+/// the warp passes over the weft at that crossing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Draft {
+    ends: usize,
+    picks: usize,
+    raised: Vec<bool>,
+}
+
+impl Draft {
+    /// Builds a draft from its rows, one row per pick, `rows[pick][end]`.
+    pub fn from_matrix(rows: Vec<Vec<bool>>) -> Result<Self> {
+        let picks = rows.len();
+        let ends = rows.first().map(|r| r.len()).unwrap_or(0);
+        if picks == 0 || ends == 0 {
+            return Err(Error::draft("the interlacement matrix is empty"));
+        }
+        if let Some(pick) = rows.iter().position(|r| r.len() != ends) {
+            return Err(Error::draft(format!(
+                "row {} has {} cells but the first one has {ends}",
+                pick + 1,
+                rows[pick].len()
+            )));
+        }
+        Ok(Self {
+            ends,
+            picks,
+            raised: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Builds a draft from a loom setup. Shafts and treadles are numbered from 0,
+    /// `tie_up[shaft][treadle]` tells whether the shaft is lifted by the treadle.
+    pub fn from_loom(
+        threading: &[usize],
+        tie_up: &[Vec<bool>],
+        treadling: &[usize],
+    ) -> Result<Self> {
+        if let Some((end, shaft)) = threading
+            .iter()
+            .enumerate()
+            .find(|(_, s)| **s >= tie_up.len())
+        {
+            return Err(Error::draft(format!(
+                "end {} is threaded on shaft {} but the tie-up has {} shafts",
+                end + 1,
+                shaft + 1,
+                tie_up.len()
+            )));
+        }
+        let treadles = tie_up.first().map(|t| t.len()).unwrap_or(0);
+        if let Some(shaft) = tie_up.iter().position(|t| t.len() != treadles) {
+            return Err(Error::draft(format!(
+                "shaft {} ties {} treadles but the first one ties {treadles}",
+                shaft + 1,
+                tie_up[shaft].len()
+            )));
+        }
+        if let Some((pick, treadle)) = treadling.iter().enumerate().find(|(_, t)| **t >= treadles) {
+            return Err(Error::draft(format!(
+                "pick {} uses treadle {} but the tie-up has {treadles} treadles",
+                pick + 1,
+                treadle + 1
+            )));
+        }
+        Self::from_matrix(
+            treadling
+                .iter()
+                .map(|treadle| {
+                    threading
+                        .iter()
+                        .map(|shaft| tie_up[*shaft][*treadle])
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// Parses rows such as `"x.x."`, where `x`, `X`, `#` or `1` mark a raised warp
+    /// and `.`, `-`, `o` or `0` a lowered one.
+    pub fn parse_rows<S: AsRef<str>>(rows: &[S]) -> Result<Vec<Vec<bool>>> {
+        rows.iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.as_ref()
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(|c| match c {
+                        'x' | 'X' | '#' | '1' => Ok(true),
+                        '.' | '-' | 'o' | '0' => Ok(false),
+                        _ => Err(Error::draft(format!(
+                            "unexpected character `{c}` in row {}",
+                            i + 1
+                        ))),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn from_fn(ends: usize, picks: usize, raised: impl Fn(usize, usize) -> bool) -> Self {
+        Self {
+            ends,
+            picks,
+            raised: (0..picks)
+                .flat_map(|pick| (0..ends).map(move |end| (end, pick)))
+                .map(|(end, pick)| raised(end, pick))
+                .collect(),
+        }
+    }
+
+    pub fn plain() -> Self {
+        Self::from_fn(2, 2, |end, pick| (end + pick).is_multiple_of(2))
+    }
+
+    /// `up`/`down` twill, the diagonal going up to the right for a right hand twill.
+    pub fn twill(up: usize, down: usize, right_hand: bool) -> Self {
+        let n = up + down;
+        Self::from_fn(n, n, |end, pick| {
+            let shift = if right_hand { n - pick % n } else { pick };
+            (end + shift) % n < up
+        })
+    }
+
+    /// Satin built from the sequence of ends interlacing on each pick. A warp faced satin only
+    /// lowers the interlacing end, a weft faced sateen only raises it.
+    fn satin_from_sequence(sequence: &[usize], warp_faced: bool) -> Self {
+        let n = sequence.len();
+        Self::from_fn(n, n, |end, pick| (sequence[pick] == end) != warp_faced)
+    }
+
+    /// 4 harness broken (crowfoot) satin, there is no regular 4 harness satin.
+    pub fn satin_4(warp_faced: bool) -> Self {
+        Self::satin_from_sequence(&[0, 2, 1, 3], warp_faced)
+    }
+
+    /// 5 harness satin with a counter of 2.
+    pub fn satin_5(warp_faced: bool) -> Self {
+        Self::satin_from_sequence(&[0, 2, 4, 1, 3], warp_faced)
+    }
+
+    /// Plain weave where every warp and weft is made of `n` parallel threads.
+    pub fn basket(n: usize) -> Self {
+        Self::from_fn(2 * n, 2 * n, |end, pick| {
+            (end / n + pick / n).is_multiple_of(2)
+        })
+    }
+
+    /// Every warp passes over `n` picks, producing ribs across the fabric.
+    pub fn warp_rib(n: usize) -> Self {
+        Self::from_fn(2, 2 * n, |end, pick| (end + pick / n).is_multiple_of(2))
+    }
+
+    /// Every weft passes over `n` ends, producing ribs along the fabric.
+    pub fn weft_rib(n: usize) -> Self {
+        Self::from_fn(2 * n, 2, |end, pick| (end / n + pick).is_multiple_of(2))
+    }
+
+    /// Number of ends and picks of one repeat of the draft.
+    pub fn repeat(&self) -> (usize, usize) {
+        (self.ends, self.picks)
+    }
+
+    /// Whether the warp `end` passes over the weft `pick`, both wrapping around the repeat.
+    pub fn warp_over(&self, end: u32, pick: u32) -> bool {
+        let end = end as usize % self.ends;
+        let pick = pick as usize % self.picks;
+        self.raised[pick * self.ends + end]
+    }
+}
+
+impl Default for Draft {
+    fn default() -> Self {
+        Self::plain()
+    }
+}
+
+/// Named weave structures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WeavePreset {
+    /// Plain weave (toile), one over one under
+    Plain,
+    /// 2/1 warp faced twill, right hand diagonal
+    #[value(name = "twill-2-1-right")]
+    #[serde(rename = "twill-2-1-right")]
+    Twill21Right,
+    /// 2/1 warp faced twill, left hand diagonal
+    #[value(name = "twill-2-1-left")]
+    #[serde(rename = "twill-2-1-left")]
+    Twill21Left,
+    /// 2/2 balanced twill (serge), right hand diagonal
+    #[value(name = "twill-2-2-right")]
+    #[serde(rename = "twill-2-2-right")]
+    Twill22Right,
+    /// 2/2 balanced twill (serge), left hand diagonal
+    #[value(name = "twill-2-2-left")]
+    #[serde(rename = "twill-2-2-left")]
+    Twill22Left,
+    /// 4 harness warp faced broken satin
+    #[value(name = "satin-4")]
+    #[serde(rename = "satin-4")]
+    Satin4,
+    /// 4 harness weft faced broken sateen
+    #[value(name = "sateen-4")]
+    #[serde(rename = "sateen-4")]
+    Sateen4,
+    /// 5 harness warp faced satin
+    #[value(name = "satin-5")]
+    #[serde(rename = "satin-5")]
+    Satin5,
+    /// 5 harness weft faced sateen
+    #[value(name = "sateen-5")]
+    #[serde(rename = "sateen-5")]
+    Sateen5,
+    /// 2x2 basket weave (natté)
+    #[value(name = "basket-2x2")]
+    #[serde(rename = "basket-2x2")]
+    Basket2x2,
+    /// Warp rib, every warp floats over two picks
+    WarpRib,
+    /// Weft rib, every weft floats over two ends
+    WeftRib,
+}
+
+impl WeavePreset {
+    pub fn draft(&self) -> Draft {
+        match self {
+            WeavePreset::Plain => Draft::plain(),
+            WeavePreset::Twill21Right => Draft::twill(2, 1, true),
+            WeavePreset::Twill21Left => Draft::twill(2, 1, false),
+            WeavePreset::Twill22Right => Draft::twill(2, 2, true),
+            WeavePreset::Twill22Left => Draft::twill(2, 2, false),
+            WeavePreset::Satin4 => Draft::satin_4(true),
+            WeavePreset::Sateen4 => Draft::satin_4(false),
+            WeavePreset::Satin5 => Draft::satin_5(true),
+            WeavePreset::Sateen5 => Draft::satin_5(false),
+            WeavePreset::Basket2x2 => Draft::basket(2),
+            WeavePreset::WarpRib => Draft::warp_rib(2),
+            WeavePreset::WeftRib => Draft::weft_rib(2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows of one repeat of `draft`, `x` where the warp is raised.
+    fn rows(draft: &Draft) -> Vec<String> {
+        let (ends, picks) = draft.repeat();
+        (0..picks as u32)
+            .map(|pick| {
+                (0..ends as u32)
+                    .map(|end| if draft.warp_over(end, pick) { 'x' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn preset_matrices() {
+        let expected: [(WeavePreset, &[&str]); 12] = [
+            (WeavePreset::Plain, &["x.", ".x"]),
+            (WeavePreset::Twill21Right, &["xx.", ".xx", "x.x"]),
+            (WeavePreset::Twill21Left, &["xx.", "x.x", ".xx"]),
+            (WeavePreset::Twill22Right, &["xx..", ".xx.", "..xx", "x..x"]),
+            (WeavePreset::Twill22Left, &["xx..", "x..x", "..xx", ".xx."]),
+            (WeavePreset::Satin4, &[".xxx", "xx.x", "x.xx", "xxx."]),
+            (WeavePreset::Sateen4, &["x...", "..x.", ".x..", "...x"]),
+            (
+                WeavePreset::Satin5,
+                &[".xxxx", "xx.xx", "xxxx.", "x.xxx", "xxx.x"],
+            ),
+            (
+                WeavePreset::Sateen5,
+                &["x....", "..x..", "....x", ".x...", "...x."],
+            ),
+            (WeavePreset::Basket2x2, &["xx..", "xx..", "..xx", "..xx"]),
+            (WeavePreset::WarpRib, &["x.", "x.", ".x", ".x"]),
+            (WeavePreset::WeftRib, &["xx..", "..xx"]),
+        ];
+        for (preset, matrix) in expected {
+            let draft = preset.draft();
+            assert_eq!(rows(&draft), matrix, "{preset:?}");
+            assert_eq!(
+                draft,
+                Draft::from_matrix(Draft::parse_rows(matrix).unwrap()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn presets_interlace_every_thread() {
+        for preset in WeavePreset::value_variants() {
+            let draft = preset.draft();
+            let (ends, picks) = draft.repeat();
+            for end in 0..ends as u32 {
+                let over = (0..picks as u32).filter(|pick| draft.warp_over(end, *pick));
+                assert!((1..picks).contains(&over.count()), "{preset:?} end {end}");
+            }
+            for pick in 0..picks as u32 {
+                let over = (0..ends as u32).filter(|end| draft.warp_over(*end, pick));
+                assert!((1..ends).contains(&over.count()), "{preset:?} pick {pick}");
+            }
+        }
+    }
+
+    #[test]
+    fn warp_over_wraps_around_the_repeat() {
+        let draft = Draft::twill(2, 1, true);
+        for (end, pick) in [(0, 0), (1, 2), (2, 1)] {
+            assert_eq!(
+                draft.warp_over(end, pick),
+                draft.warp_over(end + 3, pick + 6)
+            );
+        }
+    }
+
+    #[test]
+    fn loom_setup_gives_its_interlacement() {
+        let tie_up = Draft::parse_rows(&["xx..", ".xx.", "..xx", "x..x"]).unwrap();
+        let draft = Draft::from_loom(&[0, 1, 2, 3], &tie_up, &[0, 1, 2, 3]).unwrap();
+        assert_eq!(rows(&draft), ["x..x", "xx..", ".xx.", "..xx"]);
+    }
+
+    #[test]
+    fn parse_rows_accepts_every_marker() {
+        assert_eq!(
+            Draft::parse_rows(&["xX#1", ".-o0", "x . x ."]).unwrap(),
+            [
+                vec![true; 4],
+                vec![false; 4],
+                vec![true, false, true, false]
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_drafts() {
+        assert_eq!(
+            Draft::parse_rows(&["x.", "x?"]).unwrap_err().to_string(),
+            "invalid draft: unexpected character `?` in row 2"
+        );
+        assert_eq!(
+            Draft::from_matrix(Vec::new()).unwrap_err().to_string(),
+            "invalid draft: the interlacement matrix is empty"
+        );
+        assert_eq!(
+            Draft::from_matrix(vec![vec![true, false], vec![true]])
+                .unwrap_err()
+                .to_string(),
+            "invalid draft: row 2 has 1 cells but the first one has 2"
+        );
+
+        let tie_up = Draft::parse_rows(&["x.", ".x"]).unwrap();
+        assert_eq!(
+            Draft::from_loom(&[0, 2], &tie_up, &[0])
+                .unwrap_err()
+                .to_string(),
+            "invalid draft: end 2 is threaded on shaft 3 but the tie-up has 2 shafts"
+        );
+        assert_eq!(
+            Draft::from_loom(&[0, 1], &tie_up, &[0, 2])
+                .unwrap_err()
+                .to_string(),
+            "invalid draft: pick 2 uses treadle 3 but the tie-up has 2 treadles"
+        );
+        assert_eq!(
+            Draft::from_loom(&[0, 1], &[vec![true, false], vec![true]], &[0])
+                .unwrap_err()
+                .to_string(),
+            "invalid draft: shaft 2 ties 1 treadles but the first one ties 2"
+        );
+    }
+}
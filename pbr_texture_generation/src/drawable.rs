@@ -1,8 +1,69 @@
-use nalgebra::{Point2, Vector3};
+use nalgebra::{Point2, Vector2, Vector3};
+
+/// Closest approach of a point to a segment of a drawable, when the point lies on it.
+#[derive(Clone, Copy)]
+pub struct Hit {
+    pub segment: usize,
+    /// Position along the segment of the axis point whose solid tops the hit, from 0 at its
+    /// start to 1 at its end
+    pub t: f32,
+    /// Distance from the point to the axis of the segment
+    pub d: f32,
+    /// Side of the axis the point lies on, -1 or 1
+    pub side: f32,
+    /// Half width at the hit
+    pub width: f32,
+    /// Height of the surface at the hit
+    pub z: f32,
+    /// Whether the point is beyond the ends of the segment
+    pub cap: bool,
+}
+
+/// Everything the maps need to know about the surface above a point.
+#[derive(Clone, Copy)]
+pub struct SurfaceSample {
+    pub height: f32,
+    pub normal: Vector3<f32>,
+    /// Direction of the yarn projected on the fabric
+    pub tangent: Vector2<f32>,
+    pub albedo: Vector3<f32>,
+    pub roughness: f32,
+    pub sheen_color: Vector3<f32>,
+    pub sheen_roughness: f32,
+    /// Diameter of the yarn
+    pub thickness: f32,
+    /// Fraction of the light passing through the surface, all of it between the yarns
+    pub transmission: f32,
+    pub id: usize,
+    /// Fraction of the point covered by a yarn
+    pub coverage: f32,
+}
+
+impl SurfaceSample {
+    /// Sample of a point no yarn covers.
+    pub fn empty() -> Self {
+        Self {
+            height: -f32::INFINITY,
+            normal: Vector3::new(0., 0., 1.),
+            tangent: Vector2::new(1., 0.),
+            albedo: Vector3::new(0., 0., 0.),
+            roughness: 1.,
+            sheen_color: Vector3::new(0., 0., 0.),
+            sheen_roughness: 1.,
+            thickness: 0.,
+            transmission: 1.,
+            id: 0,
+            coverage: 0.,
+        }
+    }
+}
 
 pub trait Drawable {
-    fn get_height(&self, point: Point2<f32>) -> f32;
-    fn get_height_with_id(&self, point: Point2<f32>) -> (f32, usize);
-    fn get_normal(&self, point: Point2<f32>) -> Vector3<f32>;
-    fn get_albedo(&self, point: Point2<f32>) -> Vector3<f32>;
+    fn segment_count(&self) -> usize;
+    /// Bounding box of everything a segment can cover.
+    fn segment_bounds(&self, segment: usize) -> (Point2<f32>, Point2<f32>);
+    /// Best hit of `point` among `segments`.
+    fn hit(&self, point: Point2<f32>, segments: impl Iterator<Item = usize>) -> Option<Hit>;
+    /// Surface at `point`, `hit` being the result of [`Drawable::hit`] for it.
+    fn sample(&self, point: Point2<f32>, hit: &Hit) -> SurfaceSample;
 }
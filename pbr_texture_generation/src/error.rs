@@ -0,0 +1,107 @@
+use std::{fmt, io, path::PathBuf};
+
+use image::ImageError;
+
+use crate::scene::SceneError;
+
+/// Failure of building a world, rendering its maps or writing them.
+#[derive(Debug)]
+pub enum Error {
+    /// Writing `path` failed
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// Encoding the image written to `path` failed
+    Encoding {
+        path: PathBuf,
+        source: ImageError,
+    },
+    Scene(SceneError),
+    /// The weave draft is inconsistent
+    Draft {
+        message: String,
+    },
+    /// A setting, named by `key`, is out of its range
+    Settings {
+        key: String,
+        message: String,
+    },
+    /// Node `node` of wire `wire` cannot be rendered
+    Geometry {
+        wire: usize,
+        node: usize,
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Failures to write an image are I/O errors, the others encoding ones.
+    pub(crate) fn image(path: impl Into<PathBuf>, source: ImageError) -> Self {
+        match source {
+            ImageError::IoError(source) => Error::io(path, source),
+            source => Error::Encoding {
+                path: path.into(),
+                source,
+            },
+        }
+    }
+
+    pub(crate) fn draft(message: impl Into<String>) -> Self {
+        Error::Draft {
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn settings(key: &str, message: impl Into<String>) -> Self {
+        Error::Settings {
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "cannot write `{}`: {source}", path.display()),
+            Error::Encoding { path, source } => {
+                write!(f, "cannot encode `{}`: {source}", path.display())
+            }
+            Error::Scene(e) => e.fmt(f),
+            Error::Draft { message } => write!(f, "invalid draft: {message}"),
+            Error::Settings { key, message } => write!(f, "invalid `{key}`: {message}"),
+            Error::Geometry {
+                wire,
+                node,
+                message,
+            } => write!(f, "degenerate wire {wire} at node {node}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Encoding { source, .. } => Some(source),
+            Error::Scene(e) => e.source(),
+            Error::Draft { .. } | Error::Settings { .. } | Error::Geometry { .. } => None,
+        }
+    }
+}
+
+impl From<SceneError> for Error {
+    fn from(e: SceneError) -> Self {
+        Error::Scene(e)
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::HashMap, io, path::Path};
+
+use image::{DynamicImage, ImageBuffer, ImageFormat, Rgb32FImage};
+use nalgebra::Vector2;
+use serde_json::{Value, json};
+
+use crate::{
+    error::{Error, Result},
+    output::{MapKind, NormalConvention},
+    texture::linear_to_srgb,
+};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+
+/// Origin of a channel of an image packed for glTF.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Map(&'a Rgb32FImage, usize),
+    /// Channel of a map stored the other way round
+    Inverted(&'a Rgb32FImage, usize),
+    /// Channel of a colour map, encoded in sRGB as glTF expects colour textures
+    Color(&'a Rgb32FImage, usize),
+    Constant(f32),
+}
+
+impl Source<'_> {
+    fn get(&self, x: u32, y: u32) -> f32 {
+        match self {
+            Source::Map(image, c) => image.get_pixel(x, y).0[*c],
+            Source::Inverted(image, c) => 1. - image.get_pixel(x, y).0[*c],
+            Source::Color(image, c) => linear_to_srgb(image.get_pixel(x, y).0[*c]),
+            Source::Constant(v) => *v,
+        }
+    }
+}
+
+/// 8 bits PNG whose channels are taken from `sources`, RGB or RGBA.
+fn pack(size: Vector2<u32>, sources: &[Source]) -> image::ImageResult<Vec<u8>> {
+    let to_u8 = |v: f32| (v.clamp(0., 1.) * 255.).round() as u8;
+    let image: DynamicImage = match sources {
+        [r, g, b] => ImageBuffer::from_fn(size.x, size.y, |x, y| {
+            image::Rgb([r, g, b].map(|s| to_u8(s.get(x, y))))
+        })
+        .into(),
+        [r, g, b, a] => ImageBuffer::from_fn(size.x, size.y, |x, y| {
+            image::Rgba([r, g, b, a].map(|s| to_u8(s.get(x, y))))
+        })
+        .into(),
+        _ => unreachable!("glTF images have 3 or 4 channels"),
+    };
+    let mut bytes = io::Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageFormat::Png)?;
+    Ok(bytes.into_inner())
+}
+
+/// Binary chunk of a GLB file along with the views into it.
+#[derive(Default)]
+struct Buffer {
+    bytes: Vec<u8>,
+    views: Vec<Value>,
+}
+
+impl Buffer {
+    /// Appends `data`, aligned on 4 bytes as accessors require, and returns its view.
+    fn push(&mut self, data: &[u8], target: Option<u32>) -> usize {
+        self.bytes.resize(self.bytes.len().next_multiple_of(4), 0);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.bytes.len(),
+            "byteLength": data.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.bytes.extend_from_slice(data);
+        self.views.push(view);
+        self.views.len() - 1
+    }
+}
+
+fn floats(values: &[[f32; 4]], components: usize) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|v| &v[..components])
+        .flat_map(|f| f.to_le_bytes())
+        .collect()
+}
+
+/// Binary glTF 2.0 asset of a plane of `size` metres facing +Z, textured with `maps`, which have
+/// been mapped to [0, 1] as when saved. The images are packed into the channels
+/// glTF expects and embedded, so that the file is self contained.
+pub fn save_glb(
+    path: &Path,
+    name: &str,
+    size: Vector2<f32>,
+    maps: &HashMap<MapKind, &Rgb32FImage>,
+    normal_convention: NormalConvention,
+) -> Result<()> {
+    let map = |kind| maps.get(&kind).copied();
+    let Some(resolution) = maps
+        .values()
+        .next()
+        .map(|image| Vector2::new(image.width(), image.height()))
+    else {
+        return Err(Error::settings("maps", "no map to export to glTF"));
+    };
+    let mut buffer = Buffer::default();
+
+    // Corners counterclockwise from the bottom left, the top of the maps at +Y.
+    let (x, y) = (0.5 * size.x, 0.5 * size.y);
+    let positions = [
+        [-x, -y, 0., 0.],
+        [x, -y, 0., 0.],
+        [x, y, 0., 0.],
+        [-x, y, 0., 0.],
+    ];
+    let normals = [[0., 0., 1., 0.]; 4];
+    let tangents = [[1., 0., 0., 1.]; 4];
+    let uvs = [
+        [0., 1., 0., 0.],
+        [1., 1., 0., 0.],
+        [1., 0., 0., 0.],
+        [0., 0., 0., 0.],
+    ];
+    let indices: Vec<u8> = [0u16, 1, 2, 0, 2, 3]
+        .iter()
+        .flat_map(|i| i.to_le_bytes())
+        .collect();
+    let accessor = |view, kind, count| json!({ "bufferView": view, "componentType": FLOAT, "count": count, "type": kind });
+    let mut accessors = vec![
+        accessor(
+            buffer.push(&floats(&positions, 3), Some(ARRAY_BUFFER)),
+            "VEC3",
+            4,
+        ),
+        accessor(
+            buffer.push(&floats(&normals, 3), Some(ARRAY_BUFFER)),
+            "VEC3",
+            4,
+        ),
+        accessor(
+            buffer.push(&floats(&tangents, 4), Some(ARRAY_BUFFER)),
+            "VEC4",
+            4,
+        ),
+        accessor(buffer.push(&floats(&uvs, 2), Some(ARRAY_BUFFER)), "VEC2", 4),
+    ];
+    accessors[0]["min"] = json!([-x, -y, 0.]);
+    accessors[0]["max"] = json!([x, y, 0.]);
+    accessors.push(json!({
+        "bufferView": buffer.push(&indices, Some(ELEMENT_ARRAY_BUFFER)),
+        "componentType": UNSIGNED_SHORT,
+        "count": 6,
+        "type": "SCALAR",
+    }));
+
+    let mut images = Vec::new();
+    let mut texture = |sources: &[Source]| {
+        let png = pack(resolution, sources).map_err(|e| Error::image(path, e))?;
+        let view = buffer.push(&png, None);
+        images.push(json!({ "bufferView": view, "mimeType": "image/png" }));
+        Ok::<_, Error>(json!({ "index": images.len() - 1 }))
+    };
+    let channel = |kind, c| map(kind).map_or(Source::Constant(1.), |image| Source::Map(image, c));
+
+    let mut pbr = json!({ "metallicFactor": 0., "roughnessFactor": 1. });
+    let mut material = json!({
+        "name": name,
+        "doubleSided": true,
+    });
+    let mut extensions = serde_json::Map::new();
+    if map(MapKind::Albedo).is_some() || map(MapKind::Alpha).is_some() {
+        let albedo =
+            |c| map(MapKind::Albedo).map_or(Source::Constant(1.), |image| Source::Color(image, c));
+        let info = match map(MapKind::Alpha) {
+            Some(alpha) => {
+                material["alphaMode"] = json!("MASK");
+                material["alphaCutoff"] = json!(0.5);
+                texture(&[albedo(0), albedo(1), albedo(2), Source::Map(alpha, 0)])?
+            }
+            None => texture(&[albedo(0), albedo(1), albedo(2)])?,
+        };
+        pbr["baseColorTexture"] = info;
+    }
+    // Occlusion in red and roughness in green share a texture, the material is not metallic.
+    if map(MapKind::AmbientOcclusion).is_some() || map(MapKind::Roughness).is_some() {
+        let info = texture(&[
+            channel(MapKind::AmbientOcclusion, 0),
+            channel(MapKind::Roughness, 0),
+            Source::Constant(0.),
+        ])?;
+        if map(MapKind::AmbientOcclusion).is_some() {
+            material["occlusionTexture"] = info.clone();
+        }
+        if map(MapKind::Roughness).is_some() {
+            pbr["metallicRoughnessTexture"] = info;
+        }
+    }
+    if map(MapKind::Roughness).is_none() {
+        // Default roughness of the yarns.
+        pbr["roughnessFactor"] = json!(0.7);
+    }
+    if let Some(normal) = map(MapKind::Normal) {
+        // glTF normal maps follow the OpenGL convention.
+        let green = match normal_convention {
+            NormalConvention::OpenGl => Source::Map(normal, 1),
+            NormalConvention::DirectX => Source::Inverted(normal, 1),
+        };
+        material["normalTexture"] =
+            texture(&[Source::Map(normal, 0), green, Source::Map(normal, 2)])?;
+    }
+    // The sheen roughness is read from the alpha channel of the sheen colour.
+    if let Some(color) = map(MapKind::SheenColor) {
+        let info = texture(&[
+            Source::Color(color, 0),
+            Source::Color(color, 1),
+            Source::Color(color, 2),
+            channel(MapKind::SheenRoughness, 0),
+        ])?;
+        extensions.insert(
+            "KHR_materials_sheen".to_string(),
+            json!({
+                "sheenColorFactor": [1., 1., 1.],
+                "sheenColorTexture": info,
+                "sheenRoughnessFactor": 1.,
+                "sheenRoughnessTexture": info,
+            }),
+        );
+    }
+    if let Some(transmission) = map(MapKind::Transmission) {
+        let info = texture(&[Source::Map(transmission, 0); 3])?;
+        extensions.insert(
+            "KHR_materials_transmission".to_string(),
+            json!({ "transmissionFactor": 1., "transmissionTexture": info }),
+        );
+    }
+    material["pbrMetallicRoughness"] = pbr;
+    let extensions_used: Vec<_> = extensions.keys().cloned().collect();
+    if !extensions.is_empty() {
+        material["extensions"] = Value::Object(extensions);
+    }
+
+    let textures: Vec<_> = (0..images.len())
+        .map(|i| json!({ "sampler": 0, "source": i }))
+        .collect();
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": env!("CARGO_PKG_NAME") },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": name, "mesh": 0 }],
+        "meshes": [{
+            "name": name,
+            "primitives": [{
+                "attributes": { "POSITION": 0, "NORMAL": 1, "TANGENT": 2, "TEXCOORD_0": 3 },
+                "indices": 4,
+                "material": 0,
+            }],
+        }],
+        "materials": [material],
+        "textures": textures,
+        "images": images,
+        // Linear filtering with mipmaps, repeating since the maps tile.
+        "samplers": [{ "magFilter": 9729, "minFilter": 9987, "wrapS": 10497, "wrapT": 10497 }],
+        "accessors": accessors,
+        "bufferViews": buffer.views,
+        "buffers": [{ "byteLength": buffer.bytes.len().next_multiple_of(4) }],
+    });
+    if !extensions_used.is_empty() {
+        document["extensionsUsed"] = json!(extensions_used);
+    }
+
+    let mut json = serde_json::to_vec(&document).map_err(|e| Error::io(path, e.into()))?;
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut bin = buffer.bytes;
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let mut glb = Vec::with_capacity(28 + json.len() + bin.len());
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&((28 + json.len() + bin.len()) as u32).to_le_bytes());
+    for (chunk, kind) in [(&json, b"JSON"), (&bin, b"BIN\0")] {
+        glb.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        glb.extend_from_slice(kind);
+        glb.extend_from_slice(chunk);
+    }
+    std::fs::write(path, glb).map_err(|e| Error::io(path, e))
+}
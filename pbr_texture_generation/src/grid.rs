@@ -0,0 +1,268 @@
+use nalgebra::{Point2, Vector2};
+
+use crate::drawable::Drawable;
+
+/// Segment registered in a cell of a [`SegmentGrid`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GridEntry {
+    pub drawable: u32,
+    /// Tile the segment lies in, relative to the cell, when the grid wraps around a period
+    pub tile: (i32, i32),
+    pub segment: u32,
+}
+
+/// Upper bound on the number of cells along each axis.
+const MAX_CELLS: usize = 2048;
+
+/// Uniform grid bucketing the segments of drawables by their bounding box, so that a query only
+/// visits the segments around the point.
+///
+/// When the world tiles, the grid covers exactly one tile and wraps around it, a segment
+/// crossing the tile border being registered in the cells of the other side along with the tile
+/// it has to be looked up from.
+#[derive(Default)]
+pub struct SegmentGrid {
+    origin: Point2<f32>,
+    cell_size: Vector2<f32>,
+    cells: Vector2<usize>,
+    period: Option<Vector2<f32>>,
+    /// Entries of cell `i` are `entries[starts[i]..starts[i + 1]]`
+    starts: Vec<usize>,
+    entries: Vec<GridEntry>,
+}
+
+impl SegmentGrid {
+    pub fn build<D: Drawable>(drawables: &[D], period: Option<Vector2<f32>>) -> Self {
+        let bounds: Vec<(u32, u32, Point2<f32>, Point2<f32>)> = drawables
+            .iter()
+            .enumerate()
+            .flat_map(|(i, d)| {
+                (0..d.segment_count()).map(move |s| {
+                    let (min, max) = d.segment_bounds(s);
+                    (i as u32, s as u32, min, max)
+                })
+            })
+            .collect();
+        if bounds.is_empty() {
+            return Self::default();
+        }
+
+        // Cells about as large as a segment keep a handful of entries per cell.
+        let mean_size = bounds
+            .iter()
+            .map(|(_, _, min, max)| (max - min).max())
+            .sum::<f32>()
+            / bounds.len() as f32;
+        let (origin, size) = match period {
+            Some(period) => (Point2::origin(), period),
+            None => {
+                let min = bounds
+                    .iter()
+                    .fold(Point2::from(Vector2::repeat(f32::INFINITY)), |acc, b| {
+                        acc.inf(&b.2)
+                    });
+                let max = bounds
+                    .iter()
+                    .fold(Point2::from(Vector2::repeat(-f32::INFINITY)), |acc, b| {
+                        acc.sup(&b.3)
+                    });
+                (min, max - min)
+            }
+        };
+        let cells = size.map(|s| ((s / mean_size).ceil() as usize).clamp(1, MAX_CELLS));
+        let cell_size = size.component_div(&cells.map(|c| c as f32));
+
+        let mut buckets: Vec<Vec<GridEntry>> = vec![vec![]; cells.x * cells.y];
+        for (drawable, segment, min, max) in bounds {
+            let first = ((min - origin).component_div(&cell_size)).map(|c| c.floor() as i64);
+            let last = ((max - origin).component_div(&cell_size)).map(|c| c.floor() as i64);
+            for i in first.x..=last.x {
+                for j in first.y..=last.y {
+                    let (x, tile_x) = Self::wrap(i, cells.x, period.is_some());
+                    let (y, tile_y) = Self::wrap(j, cells.y, period.is_some());
+                    if let (Some(x), Some(y)) = (x, y) {
+                        buckets[y * cells.x + x].push(GridEntry {
+                            drawable,
+                            tile: (tile_x, tile_y),
+                            segment,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut starts = Vec::with_capacity(buckets.len() + 1);
+        let mut entries = Vec::with_capacity(buckets.iter().map(Vec::len).sum());
+        starts.push(0);
+        for mut bucket in buckets {
+            bucket.sort_unstable();
+            entries.extend(bucket);
+            starts.push(entries.len());
+        }
+
+        Self {
+            origin,
+            cell_size,
+            cells,
+            period,
+            starts,
+            entries,
+        }
+    }
+
+    /// Cell index and tile of an unwrapped cell coordinate, `None` outside a grid that does not
+    /// wrap.
+    fn wrap(i: i64, cells: usize, periodic: bool) -> (Option<usize>, i32) {
+        let n = cells as i64;
+        if periodic {
+            (Some(i.rem_euclid(n) as usize), i.div_euclid(n) as i32)
+        } else if (0..n).contains(&i) {
+            (Some(i as usize), 0)
+        } else {
+            (None, 0)
+        }
+    }
+
+    /// Groups of segments of the same drawable that may cover `point`, with the image of the
+    /// point they have to be tested against.
+    pub fn candidates(
+        &self,
+        point: Point2<f32>,
+    ) -> impl Iterator<Item = (usize, Point2<f32>, &[GridEntry])> {
+        let (point, period) = match self.period {
+            Some(period) => (
+                Point2::new(point.x.rem_euclid(period.x), point.y.rem_euclid(period.y)),
+                period,
+            ),
+            None => (point, Vector2::zeros()),
+        };
+        let entries = self.cell(point).map_or(&[][..], |cell| {
+            &self.entries[self.starts[cell]..self.starts[cell + 1]]
+        });
+        entries
+            .chunk_by(|a, b| a.drawable == b.drawable && a.tile == b.tile)
+            .map(move |chunk| {
+                let tile = Vector2::new(chunk[0].tile.0 as f32, chunk[0].tile.1 as f32);
+                (
+                    chunk[0].drawable as usize,
+                    point + tile.component_mul(&period),
+                    chunk,
+                )
+            })
+    }
+
+    fn cell(&self, point: Point2<f32>) -> Option<usize> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let c = (point - self.origin).component_div(&self.cell_size);
+        if c.x < 0. || c.y < 0. {
+            return None;
+        }
+        // A wrapped coordinate may round up to the period itself.
+        let clamp = |c: f32, n: usize| {
+            let c = c as usize;
+            if self.period.is_some() {
+                c.min(n - 1)
+            } else {
+                c
+            }
+        };
+        let (x, y) = (clamp(c.x, self.cells.x), clamp(c.y, self.cells.y));
+        (x < self.cells.x && y < self.cells.y).then_some(y * self.cells.x + x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawable::{Hit, SurfaceSample};
+
+    /// Drawable whose segments are boxes, only ever looked up through the grid.
+    struct Boxes(Vec<(Point2<f32>, Point2<f32>)>);
+
+    impl Drawable for Boxes {
+        fn segment_count(&self) -> usize {
+            self.0.len()
+        }
+
+        fn segment_bounds(&self, segment: usize) -> (Point2<f32>, Point2<f32>) {
+            self.0[segment]
+        }
+
+        fn hit(&self, _: Point2<f32>, _: impl Iterator<Item = usize>) -> Option<Hit> {
+            unimplemented!()
+        }
+
+        fn sample(&self, _: Point2<f32>, _: &Hit) -> SurfaceSample {
+            unimplemented!()
+        }
+    }
+
+    /// A drawable of boxes given as `[min x, min y, max x, max y]`.
+    fn boxes(bounds: &[[f32; 4]]) -> Vec<Boxes> {
+        vec![Boxes(
+            bounds
+                .iter()
+                .map(|b| (Point2::new(b[0], b[1]), Point2::new(b[2], b[3])))
+                .collect(),
+        )]
+    }
+
+    /// Segments found around `point` with the image of the point they are tested against.
+    fn found(grid: &SegmentGrid, point: (f32, f32)) -> Vec<(u32, (f32, f32))> {
+        let mut found: Vec<_> = grid
+            .candidates(Point2::new(point.0, point.1))
+            .flat_map(|(_, image, entries)| {
+                entries.iter().map(move |e| (e.segment, (image.x, image.y)))
+            })
+            .collect();
+        found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        found
+    }
+
+    fn assert_found(found: &[(u32, (f32, f32))], expected: &[(u32, (f32, f32))]) {
+        assert_eq!(found.len(), expected.len(), "{found:?}");
+        for ((s, p), (t, q)) in found.iter().zip(expected) {
+            assert_eq!(s, t, "{found:?}");
+            assert!(
+                (p.0 - q.0).abs() < 1e-5 && (p.1 - q.1).abs() < 1e-5,
+                "{found:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn bounded_grid_stops_at_the_segments() {
+        let drawables = boxes(&[[0., 0., 0.2, 0.2], [0.8, 0.8, 1., 1.]]);
+        let grid = SegmentGrid::build(&drawables, None);
+        assert_found(&found(&grid, (0.1, 0.1)), &[(0, (0.1, 0.1))]);
+        assert_found(&found(&grid, (0.9, 0.9)), &[(1, (0.9, 0.9))]);
+        assert_found(&found(&grid, (1.1, 0.9)), &[]);
+        assert_found(&found(&grid, (-0.1, 0.1)), &[]);
+    }
+
+    #[test]
+    fn segments_crossing_the_tile_are_found_from_the_other_side() {
+        let period = Vector2::new(1., 1.);
+        let drawables = boxes(&[[0.9, 0.4, 1.1, 0.6], [0.4, -0.1, 0.6, 0.1]]);
+        let grid = SegmentGrid::build(&drawables, Some(period));
+        // Looked up on the right, and on the left where the point is moved by a period.
+        assert_found(&found(&grid, (0.95, 0.5)), &[(0, (0.95, 0.5))]);
+        assert_found(&found(&grid, (0.05, 0.5)), &[(0, (1.05, 0.5))]);
+        assert_found(&found(&grid, (0.5, 0.95)), &[(1, (0.5, -0.05))]);
+        assert_found(&found(&grid, (0.5, 0.05)), &[(1, (0.5, 0.05))]);
+    }
+
+    #[test]
+    fn points_wrap_around_the_tile() {
+        let period = Vector2::new(2., 1.);
+        let drawables = boxes(&[[2.2, 0.2, 2.4, 0.4], [0.2, 0.2, 0.4, 0.4]]);
+        let grid = SegmentGrid::build(&drawables, Some(period));
+        // Whichever tile the point is in, it is tested against the box it wraps to.
+        for offset in [(0., 0.), (2., 0.), (-2., 3.), (4., -1.)] {
+            let point = (0.3 + offset.0, 0.3 + offset.1);
+            assert_found(&found(&grid, point), &[(0, (2.3, 0.3)), (1, (0.3, 0.3))]);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use clap::ValueEnum;
+use nalgebra::{Point3, RealField, Vector2};
+use noise::Perlin;
+use serde::Deserialize;
+
+use crate::{
+    World,
+    spline::Curve,
+    weave::{NoiseParameters, YarnParameters, periodic_noise_2d},
+    wire::{Wire, WireNode},
+};
+
+/// Stitch structures of weft knitted fabrics.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KnitStitch {
+    /// Single jersey, every loop knitted
+    Jersey,
+    /// 1x1 rib, knit and purl wales alternating
+    #[value(name = "rib-1x1")]
+    #[serde(rename = "rib-1x1")]
+    Rib1x1,
+    /// 2x2 rib, two knit wales then two purl wales
+    #[value(name = "rib-2x2")]
+    #[serde(rename = "rib-2x2")]
+    Rib2x2,
+    /// Purl (links-links), knit and purl courses alternating
+    Purl,
+}
+
+impl KnitStitch {
+    /// Whether the loop of `wale` in `course` is a knit loop, seen from the technical face.
+    pub fn is_knit(&self, wale: i32, course: i32) -> bool {
+        match self {
+            KnitStitch::Jersey => true,
+            KnitStitch::Rib1x1 => wale.rem_euclid(2) == 0,
+            KnitStitch::Rib2x2 => wale.rem_euclid(4) < 2,
+            KnitStitch::Purl => course.rem_euclid(2) == 0,
+        }
+    }
+
+    /// Number of wales and courses of one repeat of the stitch.
+    pub fn repeat(&self) -> (u32, u32) {
+        match self {
+            KnitStitch::Jersey => (1, 1),
+            KnitStitch::Rib1x1 => (2, 1),
+            KnitStitch::Rib2x2 => (4, 1),
+            KnitStitch::Purl => (1, 2),
+        }
+    }
+}
+
+pub struct KnitParameters {
+    pub stitch: KnitStitch,
+    /// Number of wales (x) and courses (y), multiples of the stitch repeat
+    pub count: Vector2<u32>,
+    /// Number of nodes per loop
+    pub resolution: u32,
+    /// Interpolation of the nodes, smooth curves needing fewer of them
+    pub curve: Curve,
+    /// Height of a loop relative to the course spacing, above 1 so that loops interlock
+    pub loop_height: f32,
+    /// How much the loop head widens above its legs, relative to the wale spacing
+    pub loop_spread: f32,
+    /// Amplitude of the z oscillation where loops pass over each other
+    pub depth: f32,
+    pub noise: NoiseParameters,
+    pub yarn: YarnParameters,
+    /// Size of the tile in millimetres
+    pub tile_mm: f32,
+}
+
+impl Default for KnitParameters {
+    fn default() -> Self {
+        Self {
+            stitch: KnitStitch::Jersey,
+            count: Vector2::new(16, 20),
+            resolution: 24,
+            curve: Curve::Polyline,
+            loop_height: 1.8,
+            loop_spread: 0.22,
+            depth: 0.01,
+            noise: NoiseParameters {
+                strength: 0.005,
+                ..NoiseParameters::default()
+            },
+            yarn: YarnParameters {
+                width: 0.009,
+                width_variation: 0.0005,
+                ..YarnParameters::default()
+            },
+            tile_mm: 20.,
+        }
+    }
+}
+
+impl KnitParameters {
+    /// Replaces the stitch, rounding the loop counts up to a whole number of repeats.
+    pub fn set_stitch(&mut self, stitch: KnitStitch) {
+        let (wales, courses) = stitch.repeat();
+        self.count.x = self.count.x.next_multiple_of(wales);
+        self.count.y = self.count.y.next_multiple_of(courses);
+        self.stitch = stitch;
+    }
+}
+
+/// Position of the yarn along a loop, `t` going from 0 at the left sinker to 1 at the right one
+/// through the needle loop head at 0.5. Knit loops have their legs in front of the heads and
+/// sinkers, purl loops behind them.
+fn loop_point(parameters: &KnitParameters, knit: bool, t: f32) -> Point3<f32> {
+    let tau = f32::two_pi();
+    // The second harmonic pinches the legs together low in the loop and opens the head above them.
+    let x = t - 0.3 * parameters.loop_spread * (tau * t).sin()
+        + parameters.loop_spread * (2. * tau * t).sin();
+    let y = parameters.loop_height * (0.5 - 0.5 * (tau * t).cos());
+    let z = -parameters.depth * (2. * tau * t).cos();
+    Point3::new(x, y, if knit { z } else { -z })
+}
+
+pub fn generate_knit(world: &mut World, parameters: &KnitParameters) {
+    let wales = parameters.count.x as i32;
+    let courses = parameters.count.y as i32;
+    let res = parameters.resolution;
+
+    let perlin_x: Perlin = Perlin::new(parameters.noise.seed);
+    let perlin_y: Perlin = Perlin::new(parameters.noise.seed.wrapping_add(42));
+    let perlin_scale: f64 = parameters.noise.scale;
+
+    let scale = Vector2::new(1. / wales as f32, 1. / courses as f32);
+
+    world.set_period(Some(Vector2::new(1., 1.)));
+    world.millimetres_per_unit = Some(parameters.tile_mm);
+
+    // The loops of the last course reach into the next tile, where they interlock with the first
+    // course through the wrapping of the world. The nodes beyond both ends of a course, in the
+    // neighbouring tiles, only steer the ends of the curve.
+    let curve = parameters.curve;
+    for course in 0..courses {
+        let node = |wale: i32, i: u32| {
+            let knit = parameters.stitch.is_knit(wale, course);
+            let t = i as f32 / (res as f32);
+            let p = loop_point(parameters, knit, t);
+            // Loops are centred on their wale, so that the tile edges run through the middle of
+            // a loop as they run along a thread in weaves, rather than between two loops.
+            let x_pos = (wale as f32 + p.x - 0.5) * scale.x;
+            let y_pos = (course as f32 + p.y) * scale.y;
+
+            let sample = |perlin: &Perlin| {
+                periodic_noise_2d(
+                    perlin,
+                    perlin_scale * x_pos as f64,
+                    perlin_scale * y_pos as f64,
+                    perlin_scale,
+                )
+            };
+            let n = Vector2::new(sample(&perlin_x), sample(&perlin_y));
+            let offset = (parameters.noise.strength * n).map(|o| o as f32);
+            let w = parameters.yarn.width + parameters.yarn.width_variation * n.x as f32;
+
+            let loop_index = course.rem_euclid(courses) * wales + wale.rem_euclid(wales);
+            // The yarn is pressed against the loops it interlocks with where it dips in or out.
+            let crossing = 0.5 + 0.5 * (2. * f32::two_pi() * t).cos();
+            WireNode {
+                height_scale: parameters.yarn.height_scale(crossing),
+                ..WireNode::new(
+                    loop_index as usize,
+                    Point3::new(x_pos + offset.x, y_pos + offset.y, p.z),
+                    w,
+                )
+            }
+        };
+        let mut nodes: Vec<WireNode> = vec![node(-1, res - 1)];
+        for wale in 0..wales {
+            let last = if wale == wales - 1 { res + 1 } else { res };
+            nodes.extend((0..last).map(|i| node(wale, i)));
+        }
+        nodes.push(node(wales, 1));
+        world.wires_mut().push(Wire {
+            curve,
+            profile: parameters.yarn.profile(),
+            ..Wire::new_from_nodes_with_material(curve.fit(nodes), true, parameters.yarn.material())
+        });
+    }
+}
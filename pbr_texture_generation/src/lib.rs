@@ -0,0 +1,788 @@
+//! Procedural PBR maps of fabrics. A [`World`] of [`Wire`]s is built by the generators of
+//! [`weave`] and [`knit`], or from a [`scene`], then rendered by [`generate`] into a [`MapSet`].
+
+pub mod bake;
+pub mod draft;
+pub mod drawable;
+pub mod error;
+pub mod gltf;
+mod grid;
+pub mod knit;
+pub mod line;
+pub mod materialx;
+pub mod metadata;
+pub mod output;
+pub mod profile;
+pub mod sampling;
+pub mod scene;
+pub mod seam;
+pub mod spline;
+pub mod texture;
+pub mod weave;
+pub mod wire;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    f32,
+    path::Path,
+    sync::OnceLock,
+};
+
+use bake::{SurfaceField, percentile};
+use grid::SegmentGrid;
+use image::{DynamicImage, Pixel, Rgb};
+use indicatif::{ParallelProgressIterator, ProgressBar};
+use itertools::Itertools;
+use metadata::{LengthRange, Metadata};
+use nalgebra::*;
+use output::{Export, MapKind, NormalConvention, NormalSource, OutputFormat, OutputSettings};
+use seam::SeamReport;
+use texture::linear_to_srgb;
+
+pub use drawable::{Drawable, Hit, SurfaceSample};
+pub use error::{Error, Result};
+pub use profile::Profile;
+pub use spline::Curve;
+pub use texture::{Texture, TextureU8};
+pub use wire::{Material, Wire, WireNode};
+
+use rayon::prelude::*;
+
+#[derive(Default)]
+pub struct World {
+    wires: Vec<Wire>,
+    /// Size of the tile the wires repeat over, `None` when the world does not tile.
+    period: Option<Vector2<f32>>,
+    /// Millimetres covered by a unit of the world, `None` when it has no physical size.
+    pub millimetres_per_unit: Option<f32>,
+    /// Built on the first query, dropped whenever the wires or the period change.
+    grid: OnceLock<SegmentGrid>,
+}
+
+impl World {
+    pub fn new(wires: Vec<Wire>, period: Option<Vector2<f32>>) -> Self {
+        Self {
+            wires,
+            period,
+            ..Self::default()
+        }
+    }
+
+    pub fn wires(&self) -> &[Wire] {
+        &self.wires
+    }
+
+    /// Wires of the world, to be changed before it is queried again.
+    pub fn wires_mut(&mut self) -> &mut Vec<Wire> {
+        self.grid.take();
+        &mut self.wires
+    }
+
+    /// Size of the tile the wires repeat over, `None` when the world does not tile.
+    pub fn period(&self) -> Option<Vector2<f32>> {
+        self.period
+    }
+
+    pub fn set_period(&mut self, period: Option<Vector2<f32>>) {
+        self.grid.take();
+        self.period = period;
+    }
+
+    /// Checks that every wire can be rendered: finite positions, positive widths and height
+    /// scales, and segments of some length across the plane.
+    pub fn validate(&self) -> Result<()> {
+        for (w, wire) in self.wires.iter().enumerate() {
+            let degenerate = |node, message: &str| Error::Geometry {
+                wire: w,
+                node,
+                message: message.to_string(),
+            };
+            for (n, node) in wire.nodes.iter().enumerate() {
+                if !node.position.iter().all(|c| c.is_finite()) {
+                    return Err(degenerate(n, "position is not finite"));
+                }
+                if !(node.width.is_finite() && node.width > 0.) {
+                    return Err(degenerate(n, "width must be finite and strictly positive"));
+                }
+                if !(node.height_scale.is_finite() && node.height_scale > 0.) {
+                    return Err(degenerate(
+                        n,
+                        "height scale must be finite and strictly positive",
+                    ));
+                }
+            }
+            if wire.curve == Curve::Bezier && wire.nodes.len() % 3 != 1 {
+                return Err(degenerate(
+                    wire.nodes.len().saturating_sub(1),
+                    "Bezier wires need 3 nodes per segment plus one",
+                ));
+            }
+            // Smooth segments may pass through a node twice, but not stay on the same point.
+            for segment in 0..wire.segment_count() {
+                let controls = &wire.nodes[wire.curve.controls(segment)];
+                let first = controls[0].position.xy();
+                if controls
+                    .iter()
+                    .all(|c| (c.position.xy() - first).norm_squared() < f32::EPSILON)
+                {
+                    let node = wire.curve.controls(segment).end - 1;
+                    return Err(degenerate(node, "segment has no length across the plane"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn grid(&self) -> &SegmentGrid {
+        self.grid
+            .get_or_init(|| SegmentGrid::build(&self.wires, self.period))
+    }
+
+    /// Hits of `point` on every wire close to it, along with the image of the point the wire was
+    /// hit at when the world tiles.
+    fn hits(&self, point: Point2<f32>) -> impl Iterator<Item = (&Wire, Point2<f32>, Hit)> {
+        self.grid().candidates(point).filter_map(|(i, q, entries)| {
+            let wire = &self.wires[i];
+            wire.hit(q, entries.iter().map(|e| e.segment as usize))
+                .map(|hit| (wire, q, hit))
+        })
+    }
+
+    /// Surface above `point`, from a single query of the wires around it.
+    pub fn sample(&self, point: Point2<f32>) -> SurfaceSample {
+        self.highest_hit(point)
+            .map_or_else(SurfaceSample::empty, |(wire, q, hit)| wire.sample(q, &hit))
+    }
+
+    /// Wire with the highest surface above `point`.
+    fn highest_hit(&self, point: Point2<f32>) -> Option<(&Wire, Point2<f32>, Hit)> {
+        self.hits(point)
+            .max_by(|(_, _, a), (_, _, b)| a.z.total_cmp(&b.z))
+    }
+}
+
+/// Number of texture rows sampled at once by [`sample_world`].
+const SAMPLE_BAND: u32 = 64;
+
+/// Samples the world over the pixels of the maps described by `settings`, handing the samples of
+/// each pixel to `write` row after row. The sampling pattern repeats with the maps so that it
+/// does not break their seams. Rows are sampled in parallel, a band at a time, so that only a
+/// band of samples is held in memory.
+fn sample_world(
+    world: &World,
+    settings: &OutputSettings,
+    mut write: impl FnMut(Point2<u32>, &[SurfaceSample]),
+) {
+    let size = settings.size;
+    let width = size.x as usize;
+    let supersampling = &settings.supersampling;
+    let count = supersampling.count();
+    let pixel_size = settings
+        .extent
+        .cast::<f64>()
+        .component_div(&size.cast::<f64>());
+    let period = world.period.map(|p| p.cast::<f64>());
+    let progress = ProgressBar::new(size.x as u64 * size.y as u64);
+    for band in (0..size.y).step_by(SAMPLE_BAND as usize) {
+        let rows = band..(band + SAMPLE_BAND).min(size.y);
+        let samples: Vec<SurfaceSample> = (rows.start as usize * width..rows.end as usize * width)
+            .into_par_iter()
+            .flat_map_iter(|i| {
+                let (x, y) = ((i % width) as u32, (i / width) as u32);
+                // Pixels are laid out the way the world is, bottom up.
+                let pixel = Vector2::new(x, size.y - y);
+                let key = Vector2::new(pixel.x % size.x, pixel.y % size.y);
+                supersampling.offsets(key).map(move |o| {
+                    // Wrapped in double precision, so that points a period apart do not round
+                    // to different points of the tile.
+                    let p = (pixel.cast::<f64>() + o.cast::<f64>()).component_mul(&pixel_size);
+                    let p = match period {
+                        Some(period) => p.zip_map(&period, f64::rem_euclid),
+                        None => p,
+                    };
+                    world.sample(Point2::from(p.cast::<f32>()))
+                })
+            })
+            .collect();
+        progress.inc((samples.len() / count) as u64);
+        for (i, pixel) in samples.chunks(count).enumerate() {
+            let y = rows.start + (i / width) as u32;
+            write(Point2::new((i % width) as u32, y), pixel);
+        }
+    }
+    progress.finish();
+}
+
+/// Mean height of the yarns covering a pixel, the ground has no height to blend with.
+fn covered_height(samples: &[SurfaceSample]) -> f32 {
+    let (sum, count) = samples
+        .iter()
+        .filter(|s| s.coverage > 0.)
+        .fold((0., 0), |(sum, count), s| (sum + s.height, count + 1));
+    if count == 0 {
+        -f32::INFINITY
+    } else {
+        sum / count as f32
+    }
+}
+
+fn height_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let v = covered_height(samples);
+    image::Rgb([v, v, v])
+}
+
+/// Id covering most of the samples, ties going to the lowest id, or the ground when yarns cover
+/// less than half of them.
+fn id_channel(samples: &[SurfaceSample]) -> Rgb<u8> {
+    let mut ids: Vec<usize> = samples
+        .iter()
+        .filter(|s| s.coverage > 0.)
+        .map(|s| s.id)
+        .collect();
+    ids.sort_unstable();
+    let id = if 2 * ids.len() < samples.len() {
+        0
+    } else {
+        ids.chunk_by(|a, b| a == b)
+            .max_by(|a, b| a.len().cmp(&b.len()).then(b[0].cmp(&a[0])))
+            .map_or(0, |chunk| chunk[0])
+    };
+    let bytes = id.to_le_bytes();
+    image::Rgb([bytes[0], bytes[1], bytes[2]])
+}
+
+fn alpha_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let v = samples.iter().map(|s| s.coverage).sum::<f32>() / samples.len() as f32;
+    image::Rgb([v, v, v])
+}
+
+fn albedo_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let a = samples.iter().map(|s| s.albedo).sum::<Vector3<f32>>() / samples.len() as f32;
+    image::Rgb([a.x, a.y, a.z])
+}
+
+fn sheen_color_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let c = samples.iter().map(|s| s.sheen_color).sum::<Vector3<f32>>() / samples.len() as f32;
+    image::Rgb([c.x, c.y, c.z])
+}
+
+fn sheen_roughness_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let v = samples.iter().map(|s| s.sheen_roughness).sum::<f32>() / samples.len() as f32;
+    image::Rgb([v, v, v])
+}
+
+fn transmission_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let v = samples.iter().map(|s| s.transmission).sum::<f32>() / samples.len() as f32;
+    image::Rgb([v, v, v])
+}
+
+fn roughness_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let v = samples.iter().map(|s| s.roughness).sum::<f32>() / samples.len() as f32;
+    image::Rgb([v, v, v])
+}
+
+fn mean_normal(samples: &[SurfaceSample]) -> Vector3<f32> {
+    samples
+        .iter()
+        .map(|s| s.normal)
+        .sum::<Vector3<f32>>()
+        .try_normalize(f32::EPSILON)
+        .unwrap_or(Vector3::new(0., 0., 1.))
+}
+
+fn normal_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let n = mean_normal(samples);
+    image::Rgb([n.x, n.y, n.z])
+}
+
+/// Sum of the directions of the yarns covering a pixel, with their angle doubled so that opposite
+/// directions add up instead of cancelling out.
+fn doubled_tangent(samples: &[SurfaceSample]) -> Vector2<f32> {
+    samples
+        .iter()
+        .filter(|s| s.coverage > 0.)
+        .map(|s| {
+            let t = s.tangent;
+            s.coverage * Vector2::new(t.x * t.x - t.y * t.y, 2. * t.x * t.y)
+        })
+        .sum()
+}
+
+/// Mean direction of the yarns, pointing right, as a normal map would store it.
+fn tangent_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let doubled = doubled_tangent(samples);
+    let angle = 0.5 * doubled.y.atan2(doubled.x);
+    image::Rgb([angle.cos(), angle.sin(), 0.])
+}
+
+/// How much the yarns covering a pixel agree on a direction, none over the ground.
+fn anisotropy_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let v = doubled_tangent(samples).norm() / samples.len() as f32;
+    image::Rgb([v, v, v])
+}
+
+fn thickness_channel(samples: &[SurfaceSample]) -> Rgb<f32> {
+    let (sum, count) = samples
+        .iter()
+        .filter(|s| s.coverage > 0.)
+        .fold((0., 0), |(sum, count), s| (sum + s.thickness, count + 1));
+    let v = if count == 0 {
+        -f32::INFINITY
+    } else {
+        sum / count as f32
+    };
+    image::Rgb([v, v, v])
+}
+
+/// A single bent strand, useful to debug the profiles.
+pub fn generate_single_strand(world: &mut World) {
+    let nodes: Vec<WireNode> = vec![
+        WireNode::new(0, Point3::new(0.5, 0.2, 0.01), 0.05),
+        WireNode::new(1, Point3::new(0.5, 0.5, 0.01), 0.05),
+        WireNode::new(2, Point3::new(0.8, 0.5, 0.01), 0.05),
+    ];
+
+    world.wires_mut().push(Wire::new_from_nodes(nodes, true));
+}
+
+fn map_texture_range(texture: &mut Texture) {
+    texture
+        .image
+        .enumerate_pixels_mut()
+        .for_each(|(_, _, p)| p.apply(|c| (c + 1.) / 2.));
+}
+
+fn map_texture_normalize(texture: &mut Texture) {
+    let min = texture
+        .image
+        .enumerate_pixels()
+        .map(|(_, _, w)| w.0[0])
+        .filter(|x| x.abs() != f32::INFINITY)
+        .reduce(f32::min)
+        .unwrap_or(1.);
+
+    let max = texture
+        .image
+        .enumerate_pixels()
+        .map(|(_, _, w)| w.0[0])
+        .filter(|x| x.abs() != f32::INFINITY)
+        .reduce(f32::max)
+        .unwrap_or(1.);
+    texture
+        .image
+        .enumerate_pixels_mut()
+        .for_each(|(_, _, p)| p.apply(|c| (c - min) / (max - min)));
+}
+
+/// Maps signed curvatures around mid grey, the 1% strongest ones saturating.
+fn map_texture_curvature(texture: &mut Texture) {
+    let scale = percentile(texture.image.pixels().map(|p| p.0[0].abs()), 0.99).max(f32::EPSILON);
+    texture
+        .image
+        .pixels_mut()
+        .for_each(|p| p.apply(|c| 0.5 + 0.5 * (c / scale).clamp(-1., 1.)));
+}
+
+/// Maps depths below the surroundings from white to black, the 1% deepest ones saturating.
+fn map_texture_cavity(texture: &mut Texture) {
+    let scale = percentile(
+        texture.image.pixels().map(|p| p.0[0]).filter(|d| *d > 0.),
+        0.99,
+    )
+    .max(f32::EPSILON);
+    texture
+        .image
+        .pixels_mut()
+        .for_each(|p| p.apply(|c| 1. - (c / scale).clamp(0., 1.)));
+}
+
+/// Lays the ground, left at minus infinity by the channels, at the lowest value of the yarns.
+fn map_texture_ground_lowest(texture: &mut Texture) {
+    let ground = texture
+        .image
+        .pixels()
+        .map(|p| p.0[0])
+        .filter(|x| x.is_finite())
+        .reduce(f32::min)
+        .unwrap_or(0.);
+    texture
+        .image
+        .pixels_mut()
+        .for_each(|p| p.apply(|c| if c.is_finite() { c } else { ground }));
+}
+
+/// Lays the ground, left at minus infinity by the channels, at zero.
+fn map_texture_ground_zero(texture: &mut Texture) {
+    texture
+        .image
+        .pixels_mut()
+        .for_each(|p| p.apply(|c| if c.is_finite() { c } else { 0. }));
+}
+
+fn save_texture(texture: &Texture, kind: MapKind, path: &Path, format: OutputFormat) -> Result<()> {
+    let mut image = texture.image.clone();
+    // Colours are rendered linearly, as OpenEXR files keep them.
+    if kind.is_color() && format != OutputFormat::Exr {
+        image.pixels_mut().for_each(|p| p.apply(linear_to_srgb));
+    }
+    let dynamic_image = DynamicImage::from(image);
+    match format {
+        OutputFormat::Png8 => dynamic_image.into_rgb8().save(path),
+        OutputFormat::Png16 => dynamic_image.into_rgb16().save(path),
+        OutputFormat::Exr => dynamic_image.save(path),
+    }
+    .map_err(|e| Error::image(path, e))
+}
+
+/// Filter reducing the samples of a pixel to the value of a map.
+type ChannelFunction = fn(&[SurfaceSample]) -> Rgb<f32>;
+type MapFunction = fn(&mut Texture);
+/// Value of a map at a pixel of the field it is baked from.
+type BakeFunction = fn(&SurfaceField, &OutputSettings, i64, i64) -> Rgb<f32>;
+
+fn grey(v: f32) -> Rgb<f32> {
+    image::Rgb([v, v, v])
+}
+
+/// Map rendered by [`generate`].
+pub struct Map {
+    pub kind: MapKind,
+    pub texture: Texture,
+    /// Differences across the edges of the map
+    pub seams: SeamReport,
+}
+
+/// Maps of a world, along with the ids, which are exact bytes, and the physical size of both.
+pub struct MapSet {
+    pub maps: Vec<Map>,
+    pub ids: Option<(TextureU8, SeamReport)>,
+    /// Whether the maps cover a whole number of tiles of the world, without which they cannot
+    /// tile
+    pub tiles: bool,
+    pub metadata: Metadata,
+}
+
+/// Renders the maps of `world` asked for by `settings`, from a single sampling of the world.
+pub fn generate(world: &World, settings: &OutputSettings) -> Result<MapSet> {
+    settings.validate()?;
+    world.validate()?;
+
+    let (texture_size, extent) = (settings.size, settings.extent);
+
+    // Floating point maps keep heights and thicknesses as lengths.
+    let (height_map_function, thickness_map_function): (MapFunction, MapFunction) = match settings
+        .format
+    {
+        OutputFormat::Exr => (map_texture_ground_lowest, map_texture_ground_zero),
+        OutputFormat::Png8 | OutputFormat::Png16 => (map_texture_normalize, map_texture_normalize),
+    };
+    let mut channels: Vec<(MapKind, ChannelFunction, Option<MapFunction>)> = vec![
+        (MapKind::Albedo, albedo_channel, None),
+        (MapKind::Height, height_channel, Some(height_map_function)),
+        (MapKind::Alpha, alpha_channel, None),
+        (MapKind::Roughness, roughness_channel, None),
+        (MapKind::SheenColor, sheen_color_channel, None),
+        (MapKind::SheenRoughness, sheen_roughness_channel, None),
+        (MapKind::Transmission, transmission_channel, None),
+        (MapKind::Tangent, tangent_channel, Some(map_texture_range)),
+        (MapKind::Anisotropy, anisotropy_channel, None),
+        (
+            MapKind::Thickness,
+            thickness_channel,
+            Some(thickness_map_function),
+        ),
+    ];
+    let mut bakes: Vec<(MapKind, BakeFunction, Option<MapFunction>)> = vec![
+        (
+            MapKind::AmbientOcclusion,
+            |field, settings, x, y| {
+                grey(field.ambient_occlusion(x, y, &settings.ambient_occlusion))
+            },
+            None,
+        ),
+        (
+            MapKind::Curvature,
+            |field, _, x, y| grey(field.curvature(x, y)),
+            Some(map_texture_curvature),
+        ),
+        (
+            MapKind::Cavity,
+            |field, settings, x, y| grey(field.cavity(x, y, &settings.ambient_occlusion)),
+            Some(map_texture_cavity),
+        ),
+    ];
+    match settings.normal_source {
+        NormalSource::Geometry => {
+            channels.push((MapKind::Normal, normal_channel, Some(map_texture_range)))
+        }
+        NormalSource::Height => bakes.push((
+            MapKind::Normal,
+            |field, _, x, y| {
+                let n = field.height_normal(x, y);
+                image::Rgb([n.x, n.y, n.z])
+            },
+            Some(map_texture_range),
+        )),
+    }
+    let bakes: Vec<_> = bakes
+        .into_iter()
+        .filter(|(kind, _, _)| settings.maps.contains(kind))
+        .collect();
+    let mut textures: Vec<(MapKind, ChannelFunction, Option<MapFunction>, Texture)> = channels
+        .into_iter()
+        .filter(|(kind, _, _)| settings.maps.contains(kind))
+        .map(|(kind, channel, map_function)| {
+            let texture = Texture::new(texture_size.x, texture_size.y, extent);
+            (kind, channel, map_function, texture)
+        })
+        .collect();
+    let mut ids = settings
+        .maps
+        .contains(&MapKind::Ids)
+        .then(|| TextureU8::new(texture_size.x, texture_size.y, extent));
+    // Maps baked from the surface once the world has been sampled.
+    let pixel_count = texture_size.x as usize * texture_size.y as usize;
+    let mut surface = (!bakes.is_empty()).then(|| {
+        (
+            Vec::with_capacity(pixel_count),
+            Vec::with_capacity(pixel_count),
+        )
+    });
+
+    // All the maps are filled from a single sampling of the world.
+    sample_world(world, settings, |pixel, samples| {
+        for (_, channel, _, texture) in &mut textures {
+            texture.image.put_pixel(pixel.x, pixel.y, channel(samples));
+        }
+        if let Some(ids) = &mut ids {
+            ids.image.put_pixel(pixel.x, pixel.y, id_channel(samples));
+        }
+        if let Some((heights, normals)) = &mut surface {
+            heights.push(covered_height(samples));
+            normals.push(mean_normal(samples));
+        }
+    });
+
+    let mut textures: Vec<(MapKind, Option<MapFunction>, Texture)> = textures
+        .into_iter()
+        .map(|(kind, _, map_function, texture)| (kind, map_function, texture))
+        .collect();
+    if let Some((heights, normals)) = surface {
+        let field = SurfaceField::new(
+            settings.size,
+            settings.extent,
+            settings.tiles(world),
+            heights,
+            normals,
+        );
+        for (kind, bake, map_function) in bakes {
+            let mut texture = Texture::new(texture_size.x, texture_size.y, extent);
+            texture
+                .image
+                .par_enumerate_pixels_mut()
+                .progress()
+                .for_each(|(x, y, pixel)| {
+                    *pixel = bake(&field, settings, x as i64, y as i64);
+                });
+            textures.push((kind, map_function, texture));
+        }
+    }
+
+    // Normals and tangents are computed with Y up, DirectX expects it down.
+    if settings.normal_convention == NormalConvention::DirectX {
+        for (_, _, texture) in textures
+            .iter_mut()
+            .filter(|(kind, _, _)| matches!(kind, MapKind::Normal | MapKind::Tangent))
+        {
+            texture.image.pixels_mut().for_each(|p| p.0[1] = -p.0[1]);
+        }
+    }
+
+    // Lengths are exported in millimetres when the world has a physical size, along with their
+    // range so that normalised maps can be scaled back.
+    let unit_scale = world.millimetres_per_unit.unwrap_or(1.);
+    let mut ranges = BTreeMap::new();
+    for (kind, _, texture) in &mut textures {
+        if !kind.is_length() {
+            continue;
+        }
+        texture
+            .image
+            .pixels_mut()
+            .for_each(|p| p.apply(|c| c * unit_scale));
+        if let Some((min, max)) = texture
+            .image
+            .pixels()
+            .map(|p| p.0[0])
+            .filter(|x| x.is_finite())
+            .minmax()
+            .into_option()
+        {
+            ranges.insert(
+                kind.name().to_string(),
+                LengthRange {
+                    file: settings.file_name(*kind),
+                    min,
+                    max,
+                    normalized: settings.format != OutputFormat::Exr,
+                },
+            );
+        }
+    }
+
+    let maps = textures
+        .into_par_iter()
+        .map(|(kind, optional_map_function, mut texture)| {
+            if let Some(map_function) = optional_map_function {
+                map_function(&mut texture);
+            }
+            let seams = texture.seam_report(settings.format);
+            Map {
+                kind,
+                texture,
+                seams,
+            }
+        })
+        .collect();
+
+    let ids = ids.map(|ids| {
+        let seams = ids.seam_report();
+        (ids, seams)
+    });
+
+    Ok(MapSet {
+        maps,
+        ids,
+        tiles: settings.tiles(world),
+        metadata: Metadata {
+            units: if world.millimetres_per_unit.is_some() {
+                "mm"
+            } else {
+                "world"
+            },
+            format: settings.format,
+            resolution: settings.size.into(),
+            extent: (settings.extent * unit_scale).into(),
+            maps: ranges,
+        },
+    })
+}
+
+impl MapSet {
+    pub fn get(&self, kind: MapKind) -> Option<&Texture> {
+        self.maps
+            .iter()
+            .find(|map| map.kind == kind)
+            .map(|map| &map.texture)
+    }
+
+    /// Writes the maps, their metadata and the exports asked for by `settings` to its directory.
+    pub fn save(&self, settings: &OutputSettings) -> Result<()> {
+        self.maps.par_iter().try_for_each(|map| {
+            save_texture(
+                &map.texture,
+                map.kind,
+                &settings.path(map.kind),
+                settings.format,
+            )
+        })?;
+        if let Some((ids, _)) = &self.ids {
+            ids.save(settings.path(MapKind::Ids))?;
+        }
+
+        let path = settings
+            .directory
+            .join(format!("{}metadata.toml", settings.prefix));
+        self.metadata.save(&path)?;
+
+        let name = format!("{}material", settings.prefix);
+        if settings.exports.contains(&Export::Mtlx) {
+            let files: Vec<_> = self
+                .maps
+                .iter()
+                .map(|map| (map.kind, settings.file_name(map.kind)))
+                .collect();
+            let path = settings.directory.join(format!("{name}.mtlx"));
+            materialx::save_mtlx(
+                &path,
+                &name,
+                &files,
+                &self.metadata,
+                settings.normal_convention,
+            )?;
+        }
+
+        if settings.exports.contains(&Export::Glb) {
+            let maps: HashMap<MapKind, _> = self
+                .maps
+                .iter()
+                .map(|map| (map.kind, &map.texture.image))
+                .collect();
+            // glTF lengths are in metres.
+            let extent = Vector2::from(self.metadata.extent);
+            let size = match self.metadata.units {
+                "mm" => extent / 1000.,
+                _ => extent,
+            };
+            let path = settings.directory.join(format!("{name}.glb"));
+            gltf::save_glb(&path, &name, size, &maps, settings.normal_convention)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(samples: &[Option<usize>]) -> usize {
+        let samples: Vec<_> = samples
+            .iter()
+            .map(|id| match id {
+                Some(id) => SurfaceSample {
+                    id: *id,
+                    coverage: 1.,
+                    ..SurfaceSample::empty()
+                },
+                None => SurfaceSample::empty(),
+            })
+            .collect();
+        let [a, b, c] = id_channel(&samples).0;
+        u32::from_le_bytes([a, b, c, 0]) as usize
+    }
+
+    /// Straight wire across the plane at height `y`.
+    fn wire(y: f32) -> Wire {
+        let node = |i, x| WireNode::new(i, Point3::new(x, y, 0.), 0.05);
+        Wire::new_from_nodes(vec![node(0, 0.1), node(1, 0.9)], false)
+    }
+
+    #[test]
+    fn worlds_changed_after_sampling_are_sampled_again() {
+        let (first, second) = (Point2::new(0.5, 0.25), Point2::new(0.5, 0.75));
+        let mut world = World::new(vec![wire(0.25)], Some(Vector2::new(1., 1.)));
+        assert!(world.sample(first).coverage > 0.);
+        assert_eq!(world.sample(second).coverage, 0.);
+
+        world.wires_mut().push(wire(0.75));
+        assert!(world.sample(second).coverage > 0.);
+
+        // Without a period, the wires are not looked up across the tile any more.
+        assert!(world.sample(Point2::new(1.5, 0.25)).coverage > 0.);
+        world.set_period(None);
+        assert_eq!(world.sample(Point2::new(1.5, 0.25)).coverage, 0.);
+
+        world.wires_mut().clear();
+        assert_eq!(world.sample(first).coverage, 0.);
+    }
+
+    #[test]
+    fn ids_are_voted_for() {
+        assert_eq!(id(&[Some(3), Some(3), Some(5), None]), 3);
+        // Yarns covering half of the samples win over the ground, ties going to the lowest id.
+        assert_eq!(id(&[None, None, Some(7), Some(5)]), 5);
+        assert_eq!(id(&[None, None, Some(5), Some(7)]), 5);
+        assert_eq!(id(&[None, None, None, Some(5)]), 0);
+        assert_eq!(id(&[Some(9)]), 9);
+    }
+}
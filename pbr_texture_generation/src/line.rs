@@ -26,7 +26,7 @@ impl Line {
             };
         }
         let mut t = v_line.dot(&v_point) / l2;
-        let cap = t < 0. || t > 1.;
+        let cap = !(0. ..=1.).contains(&t);
         if cap {
             t = clamp(t, 0., 1.);
         }
@@ -0,0 +1,226 @@
+use std::{fmt::Write, path::Path};
+
+use crate::{
+    Error, Result,
+    metadata::Metadata,
+    output::{MapKind, NormalConvention, OutputFormat},
+};
+
+/// Image node reading one of the maps, tiled over `realworldimagesize`.
+struct ImageNode {
+    kind: MapKind,
+    /// Type of the value read from the map
+    value_type: &'static str,
+    colorspace: Option<&'static str>,
+}
+
+/// Element names are restricted to letters, digits and underscores.
+fn identifier(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// MaterialX document describing a `standard_surface` fed by `maps`, named by their file
+/// relative to the document, along with the displacement of the height map. Lengths carry the
+/// unit of the metadata, so that renderers can scale the maps to the size of the fabric.
+pub fn save_mtlx(
+    path: &Path,
+    name: &str,
+    maps: &[(MapKind, String)],
+    metadata: &Metadata,
+    normal_convention: NormalConvention,
+) -> Result<()> {
+    let file = |kind| {
+        maps.iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, file)| file.as_str())
+    };
+    // Colours are stored in sRGB in PNG files and linearly in OpenEXR ones.
+    let color_space = match metadata.format {
+        OutputFormat::Png8 | OutputFormat::Png16 => "srgb_texture",
+        OutputFormat::Exr => "lin_rec709",
+    };
+    let unit = match metadata.units {
+        "mm" => r#" unittype="distance" unit="millimeter""#,
+        _ => "",
+    };
+    let name = identifier(name);
+    let graph = format!("NG_{name}");
+
+    let images = [
+        (MapKind::Albedo, "color3", Some(color_space)),
+        // Alpha feeds the opacity colour as is.
+        (MapKind::Alpha, "color3", Some("lin_rec709")),
+        (MapKind::Roughness, "float", None),
+        (MapKind::Normal, "vector3", None),
+        (MapKind::SheenColor, "color3", Some(color_space)),
+        (MapKind::SheenRoughness, "float", None),
+        (MapKind::Transmission, "float", None),
+        (MapKind::Height, "float", None),
+    ]
+    .into_iter()
+    .map(|(kind, value_type, colorspace)| ImageNode {
+        kind,
+        value_type,
+        colorspace,
+    })
+    .filter(|image| file(image.kind).is_some());
+
+    let mut graph_nodes = String::new();
+    let mut shader_inputs = String::new();
+    let mut outputs = Vec::new();
+    let [extent_x, extent_y] = metadata.extent;
+    for image in images {
+        // Nodes and outputs share their names, the outputs being named after the shader inputs.
+        let node = format!("image_{}", image.kind.name().replace('-', "_"));
+        let colorspace = image
+            .colorspace
+            .map(|c| format!(r#" colorspace="{c}""#))
+            .unwrap_or_default();
+        let file = escape(file(image.kind).unwrap_or_default());
+        let _ = write!(
+            graph_nodes,
+            r#"    <tiledimage name="{node}" type="{value_type}">
+      <input name="file" type="filename" value="{file}"{colorspace} />
+      <input name="uvtiling" type="vector2" interfacename="uvtiling" />
+      <input name="realworldimagesize" type="vector2" value="{extent_x}, {extent_y}"{unit} />
+    </tiledimage>
+"#,
+            value_type = image.value_type,
+        );
+        outputs.push((image.kind, node));
+    }
+
+    // Shader inputs fed by the outputs of the graph, named alike.
+    let mut connections: Vec<(&str, &str, String)> = Vec::new();
+    let mut displacement = None;
+    for (kind, node) in outputs {
+        let mut output =
+            |input, value_type, node: &str| connections.push((input, value_type, node.to_string()));
+        match kind {
+            MapKind::Albedo => output("base_color", "color3", &node),
+            MapKind::Alpha => output("opacity", "color3", &node),
+            MapKind::Roughness => output("specular_roughness", "float", &node),
+            MapKind::Normal => {
+                // Normal maps are read with Y up, DirectX ones are flipped back.
+                let mut map = node.clone();
+                if normal_convention == NormalConvention::DirectX {
+                    let _ = write!(
+                        graph_nodes,
+                        r#"    <multiply name="normal_flipped" type="vector3">
+      <input name="in1" type="vector3" nodename="{node}" />
+      <input name="in2" type="vector3" value="1, -1, 1" />
+    </multiply>
+    <add name="normal_opengl" type="vector3">
+      <input name="in1" type="vector3" nodename="normal_flipped" />
+      <input name="in2" type="vector3" value="0, 1, 0" />
+    </add>
+"#
+                    );
+                    map = "normal_opengl".to_string();
+                }
+                let _ = write!(
+                    graph_nodes,
+                    r#"    <normalmap name="normal_world" type="vector3">
+      <input name="in" type="vector3" nodename="{map}" />
+    </normalmap>
+"#
+                );
+                output("normal", "vector3", "normal_world");
+            }
+            MapKind::SheenColor => {
+                let _ = writeln!(
+                    shader_inputs,
+                    r#"    <input name="sheen" type="float" value="1" />"#
+                );
+                output("sheen_color", "color3", &node);
+            }
+            MapKind::SheenRoughness => output("sheen_roughness", "float", &node),
+            MapKind::Transmission => output("transmission", "float", &node),
+            MapKind::Height => {
+                // Normalised maps span the range of the heights, floating point ones store them
+                // above the origin of the world.
+                let range = metadata.maps.get(MapKind::Height.name());
+                let (offset, scale) = match range {
+                    Some(range) if range.normalized => (0., range.max - range.min),
+                    Some(range) => (range.min, 1.),
+                    None => (0., 1.),
+                };
+                let _ = write!(
+                    graph_nodes,
+                    r#"    <subtract name="height_above_ground" type="float">
+      <input name="in1" type="float" nodename="{node}" />
+      <input name="in2" type="float" value="{offset}" />
+    </subtract>
+"#
+                );
+                let _ = writeln!(
+                    graph_nodes,
+                    r#"    <output name="displacement" type="float" nodename="height_above_ground" />"#
+                );
+                displacement = Some(scale);
+            }
+            _ => {}
+        }
+    }
+    for (input, value_type, node) in connections {
+        let _ = writeln!(
+            graph_nodes,
+            r#"    <output name="{input}" type="{value_type}" nodename="{node}" />"#
+        );
+        let _ = writeln!(
+            shader_inputs,
+            r#"    <input name="{input}" type="{value_type}" nodegraph="{graph}" output="{input}" />"#
+        );
+    }
+
+    let mut document = String::new();
+    let _ = write!(
+        document,
+        r#"<?xml version="1.0"?>
+<materialx version="1.38" colorspace="lin_rec709">
+  <!-- The maps cover {extent_x} x {extent_y} {units}, raise uvtiling to repeat them. -->
+  <nodegraph name="{graph}">
+    <input name="uvtiling" type="vector2" value="1, 1" />
+{graph_nodes}  </nodegraph>
+  <standard_surface name="SR_{name}" type="surfaceshader">
+    <input name="base" type="float" value="1" />
+    <input name="metalness" type="float" value="0" />
+    <input name="thin_walled" type="boolean" value="true" />
+{shader_inputs}  </standard_surface>
+"#,
+        units = metadata.units,
+    );
+    if let Some(scale) = displacement {
+        let _ = write!(
+            document,
+            r#"  <displacement name="DS_{name}" type="displacementshader">
+    <input name="displacement" type="float" nodegraph="{graph}" output="displacement" />
+    <input name="scale" type="float" value="{scale}"{unit} />
+  </displacement>
+"#
+        );
+    }
+    let _ = write!(
+        document,
+        r#"  <surfacematerial name="{name}" type="material">
+    <input name="surfaceshader" type="surfaceshader" nodename="SR_{name}" />
+"#
+    );
+    if displacement.is_some() {
+        let _ = writeln!(
+            document,
+            r#"    <input name="displacementshader" type="displacementshader" nodename="DS_{name}" />"#
+        );
+    }
+    document.push_str("  </surfacematerial>\n</materialx>\n");
+    std::fs::write(path, document).map_err(|e| Error::io(path, e))
+}
@@ -0,0 +1,36 @@
+use std::{collections::BTreeMap, io, path::Path};
+
+use serde::Serialize;
+
+use crate::{Error, Result, output::OutputFormat};
+
+/// Range of the lengths stored in a map.
+#[derive(Serialize)]
+pub struct LengthRange {
+    pub file: String,
+    pub min: f32,
+    pub max: f32,
+    /// Whether the map stores the lengths remapped from `min..max` to 0..1 rather than the
+    /// lengths themselves
+    pub normalized: bool,
+}
+
+/// Sidecar describing the physical size of a set of maps, so that renderers can apply the right
+/// displacement scale.
+#[derive(Serialize)]
+pub struct Metadata {
+    /// Unit of every length, `mm` when the world has a physical size and `world` otherwise
+    pub units: &'static str,
+    pub format: OutputFormat,
+    pub resolution: [u32; 2],
+    /// Size of the area covered by the maps
+    pub extent: [f32; 2],
+    pub maps: BTreeMap<String, LengthRange>,
+}
+
+impl Metadata {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = toml::to_string(self).map_err(|e| Error::io(path, io::Error::other(e)))?;
+        std::fs::write(path, text).map_err(|e| Error::io(path, e))
+    }
+}
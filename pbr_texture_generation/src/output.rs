@@ -0,0 +1,236 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    World,
+    bake::AmbientOcclusionParameters,
+    error::{Error, Result},
+    sampling::{MAX_SAMPLES, Supersampling},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MapKind {
+    Albedo,
+    Height,
+    Normal,
+    Alpha,
+    Ids,
+    Roughness,
+    AmbientOcclusion,
+    SheenColor,
+    SheenRoughness,
+    /// Light passing through the yarns and between them, for back-lit thin fabrics
+    Transmission,
+    /// Convex and concave curvature of the surface, utility map
+    Curvature,
+    /// Depth of the surface below its surroundings, utility map
+    Cavity,
+    /// Diameter of the yarns, utility map
+    Thickness,
+    /// Direction of the yarns, for anisotropic shading
+    Tangent,
+    /// Agreement of the yarns on a direction, for anisotropic shading
+    Anisotropy,
+}
+
+impl MapKind {
+    /// Maps emitted when none are asked for, the utility maps being left out.
+    pub const DEFAULT: [MapKind; 10] = [
+        MapKind::Albedo,
+        MapKind::Height,
+        MapKind::Normal,
+        MapKind::Alpha,
+        MapKind::Ids,
+        MapKind::Roughness,
+        MapKind::AmbientOcclusion,
+        MapKind::SheenColor,
+        MapKind::SheenRoughness,
+        MapKind::Transmission,
+    ];
+
+    /// Whether the map holds lengths, exported in millimetres when the world has a physical size.
+    pub fn is_length(&self) -> bool {
+        matches!(self, MapKind::Height | MapKind::Thickness)
+    }
+
+    /// Whether the map holds colours, encoded in sRGB in PNG files.
+    pub fn is_color(&self) -> bool {
+        matches!(self, MapKind::Albedo | MapKind::SheenColor)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapKind::Albedo => "albedo",
+            MapKind::Height => "height",
+            MapKind::Normal => "normal",
+            MapKind::Alpha => "alpha",
+            MapKind::Ids => "ids",
+            MapKind::Roughness => "roughness",
+            MapKind::AmbientOcclusion => "ambient-occlusion",
+            MapKind::SheenColor => "sheen-color",
+            MapKind::SheenRoughness => "sheen-roughness",
+            MapKind::Transmission => "transmission",
+            MapKind::Curvature => "curvature",
+            MapKind::Cavity => "cavity",
+            MapKind::Thickness => "thickness",
+            MapKind::Tangent => "tangent",
+            MapKind::Anisotropy => "anisotropy",
+        }
+    }
+}
+
+impl std::fmt::Display for MapKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// 8 bits per channel PNG
+    Png8,
+    /// 16 bits per channel PNG
+    Png16,
+    /// 32 bits float OpenEXR, heights and thicknesses as lengths
+    Exr,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png8 | OutputFormat::Png16 => "png",
+            OutputFormat::Exr => "exr",
+        }
+    }
+}
+
+/// Asset referencing the maps, ready to be loaded by a renderer.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Export {
+    /// Binary glTF 2.0 of a plane the size of the maps, with a material using them
+    Glb,
+    /// MaterialX document of a standard surface reading the maps
+    Mtlx,
+}
+
+/// Orientation of the Y axis of normal maps.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+pub enum NormalConvention {
+    /// Green points up the map (Y+), as expected by OpenGL, Blender or Unity
+    #[value(name = "opengl")]
+    #[serde(rename = "opengl")]
+    OpenGl,
+    /// Green points down the map (Y-), as expected by DirectX or Unreal
+    #[value(name = "directx")]
+    #[serde(rename = "directx")]
+    DirectX,
+}
+
+/// Origin of the normals of the normal map, both in the tangent space of the flat fabric.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NormalSource {
+    /// Exact normals of the yarn profiles
+    Geometry,
+    /// Finite differences of the height field, matching the height map exactly
+    Height,
+}
+
+/// What [`generate`](crate::generate) renders and where [`MapSet::save`](crate::MapSet::save)
+/// writes it.
+pub struct OutputSettings {
+    /// Size of the maps in pixels
+    pub size: Vector2<u32>,
+    /// Extent of the world covered by the maps
+    pub extent: Vector2<f32>,
+    pub directory: PathBuf,
+    /// Prepended to every file name
+    pub prefix: String,
+    pub maps: Vec<MapKind>,
+    pub format: OutputFormat,
+    pub normal_convention: NormalConvention,
+    pub normal_source: NormalSource,
+    pub supersampling: Supersampling,
+    pub ambient_occlusion: AmbientOcclusionParameters,
+    /// Assets bundling the maps, written along with them
+    pub exports: Vec<Export>,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            size: Vector2::new(1024, 1024),
+            extent: Vector2::new(1., 1.),
+            directory: PathBuf::from("."),
+            prefix: String::new(),
+            maps: MapKind::DEFAULT.to_vec(),
+            format: OutputFormat::Png8,
+            normal_convention: NormalConvention::OpenGl,
+            normal_source: NormalSource::Geometry,
+            supersampling: Supersampling::default(),
+            ambient_occlusion: AmbientOcclusionParameters::default(),
+            exports: Vec::new(),
+        }
+    }
+}
+
+impl OutputSettings {
+    /// Checks the ranges the command line and scenes enforce, for settings built by hand.
+    pub fn validate(&self) -> Result<()> {
+        if self.size.x == 0 || self.size.y == 0 {
+            return Err(Error::settings("size", "must be at least 1x1"));
+        }
+        if !self.extent.iter().all(|e| e.is_finite() && *e > 0.) {
+            return Err(Error::settings("extent", "must be strictly positive"));
+        }
+        if !(1..=MAX_SAMPLES).contains(&self.supersampling.samples) {
+            return Err(Error::settings(
+                "supersampling.samples",
+                format!("must be between 1 and {MAX_SAMPLES}"),
+            ));
+        }
+        let radius = self.ambient_occlusion.radius;
+        if !(radius.is_finite() && radius > 0.) {
+            return Err(Error::settings(
+                "ambient_occlusion.radius",
+                "must be strictly positive",
+            ));
+        }
+        if self.ambient_occlusion.samples == 0 {
+            return Err(Error::settings(
+                "ambient_occlusion.samples",
+                "must be at least 1",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn file_name(&self, map: MapKind) -> String {
+        // Ids are exact bytes, they are always written as 8 bits PNG.
+        let extension = match map {
+            MapKind::Ids => "png",
+            _ => self.format.extension(),
+        };
+        format!("{}{}.{extension}", self.prefix, map)
+    }
+
+    pub fn path(&self, map: MapKind) -> PathBuf {
+        self.directory.join(self.file_name(map))
+    }
+
+    /// Whether the maps cover a whole number of tiles of the world, so that they tile too.
+    pub fn tiles(&self, world: &World) -> bool {
+        world.period().is_some_and(|period| {
+            let tiles = self.extent.component_div(&period);
+            tiles
+                .iter()
+                .all(|t| t.round() >= 1. && (t - t.round()).abs() < 1e-4)
+        })
+    }
+}
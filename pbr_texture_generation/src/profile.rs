@@ -0,0 +1,209 @@
+use clap::ValueEnum;
+use nalgebra::Vector2;
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+/// Symmetric cross-section of a yarn, swept along its axis. Lengths are relative to the half
+/// width, `x` going from 0 on the axis to 1 at the edge.
+pub trait Profile: Sync + Send {
+    /// Height of the top of the section above the axis
+    fn get_height(&self, x: f32) -> f32;
+    /// Unit normal of the top of the section, away from the axis along x and up along y
+    fn get_normal(&self, x: f32) -> Vector2<f32>;
+}
+
+pub struct CircleProfile;
+
+impl Profile for CircleProfile {
+    fn get_height(&self, x: f32) -> f32 {
+        (1. - x * x).max(0.).sqrt()
+    }
+
+    fn get_normal(&self, x: f32) -> Vector2<f32> {
+        Vector2::new(x, self.get_height(x))
+    }
+}
+
+/// Circle squashed to `aspect` times its height.
+pub struct EllipseProfile {
+    pub aspect: f32,
+}
+
+impl Profile for EllipseProfile {
+    fn get_height(&self, x: f32) -> f32 {
+        self.aspect * CircleProfile.get_height(x)
+    }
+
+    fn get_normal(&self, x: f32) -> Vector2<f32> {
+        Vector2::new(self.aspect * x, CircleProfile.get_height(x))
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(Vector2::x())
+    }
+}
+
+/// Lens between two circular arcs meeting at sharp edges, `aspect` high on the axis, which is at
+/// most 1.
+pub struct LenticularProfile {
+    pub aspect: f32,
+}
+
+impl LenticularProfile {
+    /// Radius of the arcs passing through the edges.
+    fn radius(&self) -> f32 {
+        (1. + self.aspect * self.aspect) / (2. * self.aspect)
+    }
+}
+
+impl Profile for LenticularProfile {
+    fn get_height(&self, x: f32) -> f32 {
+        let r = self.radius();
+        (self.aspect - r + (r * r - x * x).max(0.).sqrt()).max(0.)
+    }
+
+    fn get_normal(&self, x: f32) -> Vector2<f32> {
+        let r = self.radius();
+        Vector2::new(x, (r * r - x * x).max(0.).sqrt()) / r
+    }
+}
+
+/// Flat top ending in half circles, `aspect` high, which is at most 1.
+pub struct RacetrackProfile {
+    pub aspect: f32,
+}
+
+impl RacetrackProfile {
+    /// Position across the round end, from 0 where the flat top stops to 1 at the edge.
+    fn end(&self, x: f32) -> f32 {
+        let r = self.aspect.min(1.);
+        ((x - 1. + r) / r).max(0.)
+    }
+}
+
+impl Profile for RacetrackProfile {
+    fn get_height(&self, x: f32) -> f32 {
+        self.aspect.min(1.) * CircleProfile.get_height(self.end(x))
+    }
+
+    fn get_normal(&self, x: f32) -> Vector2<f32> {
+        CircleProfile.get_normal(self.end(x))
+    }
+}
+
+/// Superellipse `x^exponent + (y / aspect)^exponent = 1`, an ellipse for an exponent of 2
+/// turning boxier above it.
+pub struct PowerEllipseProfile {
+    pub aspect: f32,
+    pub exponent: f32,
+}
+
+impl Profile for PowerEllipseProfile {
+    fn get_height(&self, x: f32) -> f32 {
+        let p = self.exponent;
+        self.aspect * (1. - x.clamp(0., 1.).powf(p)).powf(1. / p)
+    }
+
+    fn get_normal(&self, x: f32) -> Vector2<f32> {
+        let p = self.exponent;
+        let y = self.get_height(x) / self.aspect;
+        Vector2::new(x.clamp(0., 1.).powf(p - 1.), y.powf(p - 1.) / self.aspect)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(Vector2::x())
+    }
+}
+
+/// Shape of the cross-section of a yarn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrossSection {
+    /// Round yarn, ignoring the aspect
+    #[default]
+    Circle,
+    /// Round yarn squashed to the aspect
+    Ellipse,
+    /// Lens with sharp edges, as flat filaments pressed together
+    Lenticular,
+    /// Flat top with round ends, as a tape
+    Racetrack,
+    /// Between an ellipse and a rectangle, as set by the exponent
+    PowerEllipse,
+}
+
+pub struct ProfileParameters {
+    pub section: CrossSection,
+    /// Height of the cross-section over its width
+    pub aspect: f32,
+    /// Exponent of power-ellipse cross-sections, 2 for an ellipse and boxier above
+    pub exponent: f32,
+}
+
+impl Default for ProfileParameters {
+    fn default() -> Self {
+        Self {
+            section: CrossSection::Circle,
+            aspect: 1.,
+            exponent: 4.,
+        }
+    }
+}
+
+impl ProfileParameters {
+    /// Lenticular and racetrack cross-sections are at most as high as they are wide.
+    pub fn validate(&self) -> Result<()> {
+        if matches!(
+            self.section,
+            CrossSection::Lenticular | CrossSection::Racetrack
+        ) && self.aspect > 1.
+        {
+            return Err(Error::settings(
+                "aspect",
+                "must be at most 1 for lenticular and racetrack cross-sections",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn profile(&self) -> Box<dyn Profile> {
+        let aspect = self.aspect;
+        match self.section {
+            CrossSection::Circle => Box::new(CircleProfile),
+            CrossSection::Ellipse => Box::new(EllipseProfile { aspect }),
+            CrossSection::Lenticular => Box::new(LenticularProfile { aspect }),
+            CrossSection::Racetrack => Box::new(RacetrackProfile { aspect }),
+            CrossSection::PowerEllipse => Box::new(PowerEllipseProfile {
+                aspect,
+                exponent: self.exponent,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normals_are_square_to_the_heights() {
+        for section in CrossSection::value_variants() {
+            let profile = ProfileParameters {
+                section: *section,
+                aspect: 0.6,
+                exponent: 3.,
+            }
+            .profile();
+            assert!(profile.get_height(1.).abs() < 1e-6, "{section:?}");
+            let step = 1e-3;
+            for i in 1..19 {
+                let x = i as f32 / 20.;
+                let slope =
+                    (profile.get_height(x + step) - profile.get_height(x - step)) / (2. * step);
+                let expected = Vector2::new(-slope, 1.).normalize();
+                let normal = profile.get_normal(x);
+                assert!(
+                    (normal - expected).norm() < 1e-2,
+                    "{section:?} at {x}: {normal:?} != {expected:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use clap::ValueEnum;
+use nalgebra::Vector2;
+use serde::Deserialize;
+
+/// Upper bound on the number of samples per pixel along each axis.
+pub const MAX_SAMPLES: u32 = 16;
+
+/// Placement of the samples taken within a pixel.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SamplePattern {
+    /// Centres of the cells of a regular grid
+    Regular,
+    /// One point jittered at random within each cell of a regular grid
+    Stratified,
+    /// Regular grid sheared so that no two samples share a row or a column
+    RotatedGrid,
+}
+
+/// `samples` x `samples` samples per pixel laid out following `pattern`.
+#[derive(Clone, Copy)]
+pub struct Supersampling {
+    pub samples: u32,
+    pub pattern: SamplePattern,
+}
+
+impl Default for Supersampling {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            pattern: SamplePattern::Regular,
+        }
+    }
+}
+
+impl Supersampling {
+    pub fn count(&self) -> usize {
+        (self.samples * self.samples) as usize
+    }
+
+    /// Offsets of the samples of `pixel` from the point it maps to, in pixels, within
+    /// [-0.5, 0.5). The stratified jitter only depends on `pixel`, so that pixels a tile apart
+    /// sample alike.
+    pub fn offsets(&self, pixel: Vector2<u32>) -> impl Iterator<Item = Vector2<f32>> {
+        let n = self.samples;
+        let pattern = self.pattern;
+        (0..n).flat_map(move |j| {
+            (0..n).map(move |i| {
+                let within = match pattern {
+                    SamplePattern::Regular => Vector2::new(0.5, 0.5),
+                    SamplePattern::Stratified => {
+                        let h = hash(pixel.x, pixel.y, j * n + i);
+                        Vector2::new(unit(h as u32), unit((h >> 32) as u32))
+                    }
+                    // The sample of column `i` is moved along the column by `n - 1 - i` rows of
+                    // the sub-grid, that of row `j` along the row by `j` columns.
+                    SamplePattern::RotatedGrid => Vector2::new(
+                        (j as f32 + 0.5) / n as f32,
+                        ((n - 1 - i) as f32 + 0.5) / n as f32,
+                    ),
+                };
+                Vector2::new(i as f32 + within.x, j as f32 + within.y) / n as f32
+                    - Vector2::repeat(0.5)
+            })
+        })
+    }
+}
+
+/// SplitMix64 finaliser of a pixel and a sample index.
+fn hash(x: u32, y: u32, sample: u32) -> u64 {
+    let mut z = ((x as u64) << 32 | y as u64)
+        .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        .wrapping_add(sample as u64);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Maps random bits to [0, 1).
+fn unit(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERNS: [SamplePattern; 3] = [
+        SamplePattern::Regular,
+        SamplePattern::Stratified,
+        SamplePattern::RotatedGrid,
+    ];
+
+    fn offsets(samples: u32, pattern: SamplePattern, pixel: (u32, u32)) -> Vec<Vector2<f32>> {
+        Supersampling { samples, pattern }
+            .offsets(Vector2::new(pixel.0, pixel.1))
+            .collect()
+    }
+
+    #[test]
+    fn every_pattern_fills_its_pixel() {
+        for pattern in PATTERNS {
+            for n in [1, 2, 3, 4, MAX_SAMPLES] {
+                let offsets = offsets(n, pattern, (5, 7));
+                assert_eq!(offsets.len(), (n * n) as usize);
+                assert!(
+                    offsets
+                        .iter()
+                        .all(|o| o.iter().all(|c| (-0.5..0.5).contains(c))),
+                    "{offsets:?}"
+                );
+                // One sample in each cell of the sub-grid.
+                let mut cells: Vec<_> = offsets
+                    .iter()
+                    .map(|o| ((o.x + 0.5) * n as f32) as u32 + n * ((o.y + 0.5) * n as f32) as u32)
+                    .collect();
+                cells.sort_unstable();
+                assert_eq!(cells, (0..n * n).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_grid_samples_share_no_row() {
+        let n = 4;
+        let offsets = offsets(n, SamplePattern::RotatedGrid, (0, 0));
+        for axis in 0..2 {
+            let mut lines: Vec<_> = offsets
+                .iter()
+                .map(|o| ((o[axis] + 0.5) * (n * n) as f32) as u32)
+                .collect();
+            lines.sort_unstable();
+            lines.dedup();
+            assert_eq!(lines.len(), (n * n) as usize);
+        }
+    }
+
+    #[test]
+    fn stratified_jitter_depends_on_the_pixel_only() {
+        let first = offsets(4, SamplePattern::Stratified, (3, 11));
+        assert_eq!(first, offsets(4, SamplePattern::Stratified, (3, 11)));
+        assert_ne!(first, offsets(4, SamplePattern::Stratified, (4, 11)));
+        assert_ne!(first, offsets(4, SamplePattern::Regular, (3, 11)));
+    }
+}
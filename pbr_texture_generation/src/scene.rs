@@ -0,0 +1,846 @@
+use std::{collections::BTreeMap, fmt, path::Path, path::PathBuf};
+
+use nalgebra::{Vector2, Vector3};
+use serde::Deserialize;
+
+use crate::{
+    Error, World,
+    draft::{Draft, WeavePreset},
+    knit::{KnitParameters, KnitStitch, generate_knit},
+    output::{Export, MapKind, NormalConvention, NormalSource, OutputFormat},
+    profile::CrossSection,
+    sampling::{MAX_SAMPLES, SamplePattern},
+    spline::Curve,
+    weave::{NoiseParameters, WeaveParameters, YarnParameters, generate_tissage},
+};
+
+/// Version of the scene format understood by this build.
+pub const SCENE_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    Invalid {
+        key: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io { path, source } => {
+                write!(f, "cannot read scene `{}`: {source}", path.display())
+            }
+            SceneError::Parse { path, source } => {
+                write!(f, "cannot parse scene `{}`: {source}", path.display())
+            }
+            SceneError::Invalid { key, message } => write!(f, "invalid `{key}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io { source, .. } => Some(source),
+            SceneError::Parse { source, .. } => Some(source),
+            SceneError::Invalid { .. } => None,
+        }
+    }
+}
+
+fn invalid(key: &str, message: impl Into<String>) -> SceneError {
+    SceneError::Invalid {
+        key: key.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Names the scene field `key` in an error raised while building its value.
+fn rekey(key: &str, error: Error) -> SceneError {
+    match error {
+        Error::Draft { message } | Error::Settings { message, .. } => invalid(key, message),
+        error => invalid(key, error.to_string()),
+    }
+}
+
+/// Output settings of a scene, every field falling back to the command line defaults.
+#[derive(Default)]
+pub struct SceneOutput {
+    pub resolution: Option<Vector2<u32>>,
+    pub extent: Option<Vector2<f32>>,
+    pub directory: Option<PathBuf>,
+    pub prefix: Option<String>,
+    pub maps: Option<Vec<MapKind>>,
+    pub format: Option<OutputFormat>,
+    pub normal_convention: Option<NormalConvention>,
+    pub normal_source: Option<NormalSource>,
+    pub samples: Option<u32>,
+    pub sample_pattern: Option<SamplePattern>,
+    pub ao_radius: Option<f32>,
+    pub ao_samples: Option<u32>,
+    pub export: Option<Vec<Export>>,
+}
+
+pub enum Fabric {
+    Woven(WeaveParameters),
+    Knitted(KnitParameters),
+}
+
+impl Fabric {
+    pub fn noise_mut(&mut self) -> &mut NoiseParameters {
+        match self {
+            Fabric::Woven(weave) => &mut weave.noise,
+            Fabric::Knitted(knit) => &mut knit.noise,
+        }
+    }
+
+    /// Parameters of every yarn of the fabric.
+    pub fn yarns_mut(&mut self) -> Vec<&mut YarnParameters> {
+        match self {
+            Fabric::Woven(weave) => vec![&mut weave.warp, &mut weave.weft],
+            Fabric::Knitted(knit) => vec![&mut knit.yarn],
+        }
+    }
+
+    pub fn curve_mut(&mut self) -> &mut Curve {
+        match self {
+            Fabric::Woven(weave) => &mut weave.curve,
+            Fabric::Knitted(knit) => &mut knit.curve,
+        }
+    }
+}
+
+impl Default for Fabric {
+    fn default() -> Self {
+        Fabric::Woven(WeaveParameters::default())
+    }
+}
+
+#[derive(Default)]
+pub struct Scene {
+    pub fabric: Fabric,
+    pub output: SceneOutput,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    version: u32,
+    weave: Option<WeaveSection>,
+    knit: Option<KnitSection>,
+    #[serde(default)]
+    noise: NoiseSection,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialSection>,
+    #[serde(default)]
+    warp: YarnSection,
+    #[serde(default)]
+    weft: YarnSection,
+    #[serde(default)]
+    output: OutputSection,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WeaveSection {
+    threads: Option<[u32; 2]>,
+    nodes_per_crossing: Option<u32>,
+    curve: Option<Curve>,
+    crimp: Option<f32>,
+    preset: Option<WeavePreset>,
+    draft: Option<DraftSection>,
+    tile_mm: Option<f32>,
+    threads_per_cm: Option<[f32; 2]>,
+}
+
+/// Either an interlacement `matrix`, or a `threading`, `tie_up` and `treadling` with shafts and
+/// treadles numbered from 1.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DraftSection {
+    matrix: Option<Vec<String>>,
+    threading: Option<Vec<usize>>,
+    tie_up: Option<Vec<String>>,
+    treadling: Option<Vec<usize>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KnitSection {
+    stitch: Option<KnitStitch>,
+    loops: Option<[u32; 2]>,
+    nodes_per_loop: Option<u32>,
+    curve: Option<Curve>,
+    loop_height: Option<f32>,
+    loop_spread: Option<f32>,
+    depth: Option<f32>,
+    tile_mm: Option<f32>,
+    loops_per_cm: Option<[f32; 2]>,
+    #[serde(default)]
+    yarn: YarnSection,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoiseSection {
+    seed: Option<u32>,
+    scale: Option<f64>,
+    strength: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialSection {
+    color: [f32; 3],
+    roughness: Option<f32>,
+    roughness_variation: Option<f32>,
+    sheen_color: Option<[f32; 3]>,
+    sheen_roughness: Option<f32>,
+    /// Fraction of the light passing straight through `transmission_thickness_mm` of yarn
+    transmission: Option<f32>,
+    /// Defaults to the diameter of the yarn
+    transmission_thickness_mm: Option<f32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct YarnSection {
+    material: Option<String>,
+    width: Option<f32>,
+    /// Diameter of the yarn in millimetres, instead of its `width` in world units
+    diameter_mm: Option<f32>,
+    width_variation: Option<f32>,
+    profile: Option<CrossSection>,
+    /// Height of the cross-section over its width
+    aspect: Option<f32>,
+    exponent: Option<f32>,
+    crossing_flattening: Option<f32>,
+}
+
+impl YarnSection {
+    fn is_set(&self) -> bool {
+        self.material.is_some()
+            || self.width.is_some()
+            || self.diameter_mm.is_some()
+            || self.width_variation.is_some()
+            || self.profile.is_some()
+            || self.aspect.is_some()
+            || self.exponent.is_some()
+            || self.crossing_flattening.is_some()
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutputSection {
+    resolution: Option<[u32; 2]>,
+    extent: Option<[f32; 2]>,
+    directory: Option<PathBuf>,
+    prefix: Option<String>,
+    maps: Option<Vec<MapKind>>,
+    format: Option<OutputFormat>,
+    normal_convention: Option<NormalConvention>,
+    normal_source: Option<NormalSource>,
+    samples: Option<u32>,
+    sample_pattern: Option<SamplePattern>,
+    ao_radius: Option<f32>,
+    ao_samples: Option<u32>,
+    export: Option<Vec<Export>>,
+}
+
+fn check_finite<T: Into<f64> + Copy>(key: &str, v: T) -> Result<T, SceneError> {
+    if v.into().is_finite() {
+        Ok(v)
+    } else {
+        Err(invalid(key, "must be a finite number"))
+    }
+}
+
+fn check_positive<T: Into<f64> + Copy>(key: &str, v: T) -> Result<T, SceneError> {
+    if check_finite(key, v)?.into() > 0. {
+        Ok(v)
+    } else {
+        Err(invalid(key, "must be strictly positive"))
+    }
+}
+
+impl Scene {
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| SceneError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let file: SceneFile = toml::from_str(&text).map_err(|source| SceneError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self::from_file(file)?)
+    }
+
+    fn from_file(file: SceneFile) -> Result<Self, SceneError> {
+        if file.version != SCENE_VERSION {
+            return Err(invalid(
+                "version",
+                format!(
+                    "unsupported version {}, expected {SCENE_VERSION}",
+                    file.version
+                ),
+            ));
+        }
+
+        for (name, material) in &file.materials {
+            let colors = [
+                ("color", Some(material.color)),
+                ("sheen_color", material.sheen_color),
+            ];
+            for (field, color) in colors {
+                for (i, c) in color.iter().flatten().enumerate() {
+                    let key = format!("materials.{name}.{field}[{i}]");
+                    if !(0. ..=1.).contains(&check_finite(&key, *c)?) {
+                        return Err(invalid(&key, "must be between 0 and 1"));
+                    }
+                }
+            }
+            let fractions = [
+                ("roughness", material.roughness),
+                ("roughness_variation", material.roughness_variation),
+                ("sheen_roughness", material.sheen_roughness),
+                ("transmission", material.transmission),
+            ];
+            for (field, value) in fractions {
+                let key = format!("materials.{name}.{field}");
+                if let Some(v) = value
+                    && !(0. ..=1.).contains(&check_finite(&key, v)?)
+                {
+                    return Err(invalid(&key, "must be between 0 and 1"));
+                }
+            }
+            if let Some(thickness) = material.transmission_thickness_mm {
+                check_positive(
+                    &format!("materials.{name}.transmission_thickness_mm"),
+                    thickness,
+                )?;
+            }
+        }
+
+        let fabric = match (&file.weave, &file.knit) {
+            (Some(_), Some(_)) => {
+                return Err(invalid("knit", "cannot be used together with `weave`"));
+            }
+            (_, Some(knit)) => {
+                if file.warp.is_set() {
+                    return Err(invalid("warp", "only applies to woven fabrics"));
+                }
+                if file.weft.is_set() {
+                    return Err(invalid("weft", "only applies to woven fabrics"));
+                }
+                Fabric::Knitted(Self::knit(knit, &file)?)
+            }
+            (weave, None) => Fabric::Woven(Self::weave(weave.as_ref(), &file)?),
+        };
+
+        let output = &file.output;
+        if let Some([x, y]) = output.resolution
+            && (x == 0 || y == 0)
+        {
+            return Err(invalid("output.resolution", "must be at least 1x1"));
+        }
+        if let Some(extent) = output.extent {
+            for (i, e) in extent.iter().enumerate() {
+                check_positive(&format!("output.extent[{i}]"), *e)?;
+            }
+        }
+        if let Some(samples) = output.samples
+            && !(1..=MAX_SAMPLES).contains(&samples)
+        {
+            return Err(invalid(
+                "output.samples",
+                format!("must be between 1 and {MAX_SAMPLES}"),
+            ));
+        }
+        if let Some(radius) = output.ao_radius {
+            check_positive("output.ao_radius", radius)?;
+        }
+        if output.ao_samples == Some(0) {
+            return Err(invalid("output.ao_samples", "must be at least 1"));
+        }
+
+        Ok(Self {
+            fabric,
+            output: SceneOutput {
+                resolution: output.resolution.map(Vector2::from),
+                extent: output.extent.map(Vector2::from),
+                directory: file.output.directory,
+                prefix: file.output.prefix,
+                maps: file.output.maps,
+                format: file.output.format,
+                normal_convention: file.output.normal_convention,
+                normal_source: file.output.normal_source,
+                samples: file.output.samples,
+                sample_pattern: file.output.sample_pattern,
+                ao_radius: file.output.ao_radius,
+                ao_samples: file.output.ao_samples,
+                export: file.output.export,
+            },
+        })
+    }
+
+    fn weave(
+        section: Option<&WeaveSection>,
+        file: &SceneFile,
+    ) -> Result<WeaveParameters, SceneError> {
+        let mut weave = WeaveParameters::default();
+        let default_section = WeaveSection::default();
+        let section = section.unwrap_or(&default_section);
+
+        match (&section.preset, &section.draft) {
+            (Some(_), Some(_)) => {
+                return Err(invalid(
+                    "weave.preset",
+                    "cannot be used together with `weave.draft`",
+                ));
+            }
+            (Some(preset), None) => weave.set_draft(preset.draft()),
+            (None, Some(draft)) => weave.set_draft(Self::draft(draft)?),
+            (None, None) => {}
+        }
+        if let Some([x, y]) = section.threads {
+            let (ends, picks) = weave.draft.repeat();
+            let (ends, picks) = (ends as u32, picks as u32);
+            if x == 0 || y == 0 || x % ends != 0 || y % picks != 0 {
+                return Err(invalid(
+                    "weave.threads",
+                    format!(
+                        "thread counts must be non-zero multiples of the draft repeat {ends}x{picks}"
+                    ),
+                ));
+            }
+            weave.count = Vector2::new(x, y);
+        }
+        if let Some(n) = section.nodes_per_crossing {
+            if n < 2 {
+                return Err(invalid("weave.nodes_per_crossing", "must be at least 2"));
+            }
+            weave.resolution = n;
+        }
+        if let Some(curve) = section.curve {
+            weave.curve = curve;
+        }
+        if let Some(crimp) = section.crimp {
+            weave.crimp = check_finite("weave.crimp", crimp)?;
+        }
+
+        if let Some(tile_mm) = Self::tile_mm(
+            "weave",
+            section.tile_mm,
+            "threads_per_cm",
+            section.threads_per_cm,
+            weave.count,
+        )? {
+            weave.tile_mm = tile_mm;
+        }
+
+        weave.noise = Self::noise(&file.noise, weave.noise)?;
+
+        let tile_mm = weave.tile_mm;
+        weave.warp = Self::yarn("warp", &file.warp, &file.materials, tile_mm, weave.warp)?;
+        weave.weft = Self::yarn("weft", &file.weft, &file.materials, tile_mm, weave.weft)?;
+        Ok(weave)
+    }
+
+    fn knit(section: &KnitSection, file: &SceneFile) -> Result<KnitParameters, SceneError> {
+        let mut knit = KnitParameters::default();
+        if let Some(stitch) = section.stitch {
+            knit.stitch = stitch;
+        }
+        if let Some([x, y]) = section.loops {
+            let (wales, courses) = knit.stitch.repeat();
+            if x == 0 || y == 0 || x % wales != 0 || y % courses != 0 {
+                return Err(invalid(
+                    "knit.loops",
+                    format!(
+                        "loop counts must be non-zero multiples of the stitch repeat {wales}x{courses}"
+                    ),
+                ));
+            }
+            knit.count = Vector2::new(x, y);
+        }
+        if let Some(n) = section.nodes_per_loop {
+            if n < 4 {
+                return Err(invalid("knit.nodes_per_loop", "must be at least 4"));
+            }
+            knit.resolution = n;
+        }
+        if let Some(curve) = section.curve {
+            knit.curve = curve;
+        }
+        if let Some(height) = section.loop_height {
+            if check_positive("knit.loop_height", height)? <= 1. {
+                return Err(invalid(
+                    "knit.loop_height",
+                    "must be above 1 for the loops to interlock",
+                ));
+            }
+            knit.loop_height = height;
+        }
+        if let Some(spread) = section.loop_spread {
+            knit.loop_spread = check_finite("knit.loop_spread", spread)?;
+        }
+        if let Some(depth) = section.depth {
+            knit.depth = check_finite("knit.depth", depth)?;
+        }
+        if let Some(tile_mm) = Self::tile_mm(
+            "knit",
+            section.tile_mm,
+            "loops_per_cm",
+            section.loops_per_cm,
+            knit.count,
+        )? {
+            knit.tile_mm = tile_mm;
+        }
+        knit.noise = Self::noise(&file.noise, knit.noise)?;
+        knit.yarn = Self::yarn(
+            "knit.yarn",
+            &section.yarn,
+            &file.materials,
+            knit.tile_mm,
+            knit.yarn,
+        )?;
+        Ok(knit)
+    }
+
+    /// Size of the tile in millimetres, either given as `tile_mm` or derived from the number of
+    /// threads or loops per centimetre along each axis of a tile holding `count` of them.
+    fn tile_mm(
+        section: &str,
+        tile_mm: Option<f32>,
+        per_cm_field: &str,
+        per_cm: Option<[f32; 2]>,
+        count: Vector2<u32>,
+    ) -> Result<Option<f32>, SceneError> {
+        let key = format!("{section}.{per_cm_field}");
+        match (tile_mm, per_cm) {
+            (Some(_), Some(_)) => Err(invalid(&key, "cannot be used together with `tile_mm`")),
+            (Some(tile_mm), None) => {
+                check_positive(&format!("{section}.tile_mm"), tile_mm).map(Some)
+            }
+            (None, Some([x, y])) => {
+                let size_x = 10. * count.x as f32 / check_positive(&format!("{key}[0]"), x)?;
+                let size_y = 10. * count.y as f32 / check_positive(&format!("{key}[1]"), y)?;
+                // The world is isotropic, the tile has to be square.
+                if (size_x - size_y).abs() > 0.01 * size_x.max(size_y) {
+                    return Err(invalid(
+                        &key,
+                        format!(
+                            "gives a tile of {size_x:.2} x {size_y:.2} mm, the counts must keep it square"
+                        ),
+                    ));
+                }
+                Ok(Some(0.5 * (size_x + size_y)))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn noise(
+        section: &NoiseSection,
+        mut noise: NoiseParameters,
+    ) -> Result<NoiseParameters, SceneError> {
+        if let Some(seed) = section.seed {
+            noise.seed = seed;
+        }
+        if let Some(scale) = section.scale {
+            noise.scale = check_positive("noise.scale", scale)?;
+        }
+        if let Some(strength) = section.strength {
+            noise.strength = check_finite("noise.strength", strength)?;
+        }
+        Ok(noise)
+    }
+
+    fn draft(draft: &DraftSection) -> Result<Draft, SceneError> {
+        let rows = |key: &str, rows: &[String]| Draft::parse_rows(rows).map_err(|e| rekey(key, e));
+        let numbers = |key: &str, numbers: &[usize]| {
+            numbers
+                .iter()
+                .map(|n| {
+                    n.checked_sub(1)
+                        .ok_or_else(|| invalid(key, "numbering starts at 1"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        match draft {
+            DraftSection {
+                matrix: Some(matrix),
+                threading: None,
+                tie_up: None,
+                treadling: None,
+            } => Draft::from_matrix(rows("weave.draft.matrix", matrix)?)
+                .map_err(|e| rekey("weave.draft.matrix", e)),
+            DraftSection {
+                matrix: None,
+                threading: Some(threading),
+                tie_up: Some(tie_up),
+                treadling: Some(treadling),
+            } => Draft::from_loom(
+                &numbers("weave.draft.threading", threading)?,
+                &rows("weave.draft.tie_up", tie_up)?,
+                &numbers("weave.draft.treadling", treadling)?,
+            )
+            .map_err(|e| rekey("weave.draft", e)),
+            _ => Err(invalid(
+                "weave.draft",
+                "expected either `matrix` or `threading`, `tie_up` and `treadling`",
+            )),
+        }
+    }
+
+    fn yarn(
+        section: &str,
+        yarn: &YarnSection,
+        materials: &BTreeMap<String, MaterialSection>,
+        tile_mm: f32,
+        mut parameters: YarnParameters,
+    ) -> Result<YarnParameters, SceneError> {
+        if let Some(name) = &yarn.material {
+            let material = materials.get(name).ok_or_else(|| {
+                invalid(
+                    &format!("{section}.material"),
+                    format!("unknown material `{name}`"),
+                )
+            })?;
+            parameters.color = Vector3::from(material.color);
+            if let Some(roughness) = material.roughness {
+                parameters.roughness = roughness;
+            }
+            if let Some(variation) = material.roughness_variation {
+                parameters.roughness_variation = variation;
+            }
+            if let Some(sheen_color) = material.sheen_color {
+                parameters.sheen_color = Vector3::from(sheen_color);
+            }
+            if let Some(sheen_roughness) = material.sheen_roughness {
+                parameters.sheen_roughness = sheen_roughness;
+            }
+            if let Some(transmission) = material.transmission {
+                parameters.transmission = transmission;
+            }
+            if let Some(thickness) = material.transmission_thickness_mm {
+                parameters.transmission_thickness = Some(thickness / tile_mm);
+            }
+        }
+        match (yarn.width, yarn.diameter_mm) {
+            (Some(_), Some(_)) => {
+                return Err(invalid(
+                    &format!("{section}.diameter_mm"),
+                    "cannot be used together with `width`",
+                ));
+            }
+            (Some(width), None) => {
+                parameters.width = check_positive(&format!("{section}.width"), width)?;
+            }
+            (None, Some(diameter)) => {
+                let diameter = check_positive(&format!("{section}.diameter_mm"), diameter)?;
+                parameters.width = 0.5 * diameter / tile_mm;
+            }
+            (None, None) => {}
+        }
+        if let Some(variation) = yarn.width_variation {
+            let key = format!("{section}.width_variation");
+            if check_finite(&key, variation)?.abs() >= parameters.width {
+                return Err(invalid(&key, "must be smaller than the width"));
+            }
+            parameters.width_variation = variation;
+        }
+
+        let profile = &mut parameters.profile;
+        if let Some(section) = yarn.profile {
+            profile.section = section;
+        }
+        if let Some(aspect) = yarn.aspect {
+            profile.aspect = check_positive(&format!("{section}.aspect"), aspect)?;
+        }
+        profile
+            .validate()
+            .map_err(|e| rekey(&format!("{section}.aspect"), e))?;
+        if let Some(exponent) = yarn.exponent {
+            if check_finite(&format!("{section}.exponent"), exponent)? < 1. {
+                return Err(invalid(
+                    &format!("{section}.exponent"),
+                    "must be at least 1",
+                ));
+            }
+            profile.exponent = exponent;
+        }
+        if let Some(flattening) = yarn.crossing_flattening {
+            let key = format!("{section}.crossing_flattening");
+            if !(0. ..1.).contains(&check_finite(&key, flattening)?) {
+                return Err(invalid(&key, "must be between 0 and 1, excluded"));
+            }
+            parameters.crossing_flattening = flattening;
+        }
+        Ok(parameters)
+    }
+
+    pub fn build_world(&self) -> World {
+        let mut world = World::default();
+        match &self.fabric {
+            Fabric::Woven(weave) => generate_tissage(&mut world, weave),
+            Fabric::Knitted(knit) => generate_knit(&mut world, knit),
+        }
+        world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Scene, SceneError> {
+        let file: SceneFile = toml::from_str(text).map_err(|source| SceneError::Parse {
+            path: PathBuf::from("test.toml"),
+            source,
+        })?;
+        Scene::from_file(file)
+    }
+
+    /// Key of the field `text` is rejected for.
+    fn invalid_key(text: &str) -> String {
+        match parse(text) {
+            Err(SceneError::Invalid { key, .. }) => key,
+            Err(e) => panic!("expected an invalid field, got {e}"),
+            Ok(_) => panic!("expected an invalid field, the scene loaded"),
+        }
+    }
+
+    #[test]
+    fn bundled_scenes_load() {
+        for name in ["toile", "serge", "jersey"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("scenes/{name}.toml"));
+            if let Err(e) = Scene::load(&path) {
+                panic!("{name}: {e}");
+            }
+        }
+    }
+
+    #[test]
+    fn minimal_scene_is_the_default_weave() {
+        let scene = parse("version = 1").unwrap();
+        let Fabric::Woven(weave) = scene.fabric else {
+            panic!("expected a woven fabric");
+        };
+        assert_eq!(weave.count, WeaveParameters::default().count);
+        assert_eq!(weave.draft, Draft::plain());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert!(matches!(
+            parse("version = 1\n[weave]\nthread = [2, 2]"),
+            Err(SceneError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_fields_name_their_key() {
+        let cases = [
+            ("version = 2", "version"),
+            ("version = 1\n[weave]\nthreads = [3, 4]", "weave.threads"),
+            (
+                "version = 1\n[weave]\npreset = \"twill-2-2-right\"\nthreads = [6, 8]",
+                "weave.threads",
+            ),
+            (
+                "version = 1\n[weave]\nnodes_per_crossing = 1",
+                "weave.nodes_per_crossing",
+            ),
+            (
+                "version = 1\n[weave]\npreset = \"plain\"\n[weave.draft]\nmatrix = [\"x.\", \".x\"]",
+                "weave.preset",
+            ),
+            (
+                "version = 1\n[weave.draft]\nmatrix = [\"x.\", \".?\"]",
+                "weave.draft.matrix",
+            ),
+            (
+                "version = 1\n[weave.draft]\nthreading = [0]\ntie_up = [\"x\"]\ntreadling = [1]",
+                "weave.draft.threading",
+            ),
+            (
+                "version = 1\n[weave]\ntile_mm = 10.0\nthreads_per_cm = [24.0, 24.0]",
+                "weave.threads_per_cm",
+            ),
+            (
+                "version = 1\n[weave]\nthreads_per_cm = [24.0, 12.0]",
+                "weave.threads_per_cm",
+            ),
+            ("version = 1\n[weave]\ntile_mm = -1.0", "weave.tile_mm"),
+            ("version = 1\n[noise]\nscale = 0.0", "noise.scale"),
+            (
+                "version = 1\n[materials.red]\ncolor = [1.0, 1.5, 0.0]",
+                "materials.red.color[1]",
+            ),
+            (
+                "version = 1\n[materials.red]\ncolor = [1.0, 0.0, 0.0]\nroughness = 2.0",
+                "materials.red.roughness",
+            ),
+            ("version = 1\n[warp]\nmaterial = \"red\"", "warp.material"),
+            (
+                "version = 1\n[warp]\nwidth = 0.01\ndiameter_mm = 0.3",
+                "warp.diameter_mm",
+            ),
+            (
+                "version = 1\n[weft]\nwidth = 0.01\nwidth_variation = 0.02",
+                "weft.width_variation",
+            ),
+            (
+                "version = 1\n[weft]\nprofile = \"lenticular\"\naspect = 1.5",
+                "weft.aspect",
+            ),
+            ("version = 1\n[knit]\n[weave]", "knit"),
+            ("version = 1\n[knit]\n[warp]\nwidth = 0.01", "warp"),
+            ("version = 1\n[knit]\nloops = [0, 4]", "knit.loops"),
+            (
+                "version = 1\n[knit]\nstitch = \"rib-2x2\"\nloops = [6, 20]",
+                "knit.loops",
+            ),
+            (
+                "version = 1\n[knit]\nstitch = \"purl\"\nloops = [16, 5]",
+                "knit.loops",
+            ),
+            (
+                "version = 1\n[knit]\nnodes_per_loop = 3",
+                "knit.nodes_per_loop",
+            ),
+            ("version = 1\n[knit]\nloop_height = 0.8", "knit.loop_height"),
+            (
+                "version = 1\n[knit.yarn]\ndiameter_mm = 0.0",
+                "knit.yarn.diameter_mm",
+            ),
+            (
+                "version = 1\n[output]\nresolution = [0, 16]",
+                "output.resolution",
+            ),
+            (
+                "version = 1\n[output]\nextent = [1.0, -1.0]",
+                "output.extent[1]",
+            ),
+            ("version = 1\n[output]\nsamples = 0", "output.samples"),
+            ("version = 1\n[output]\nao_samples = 0", "output.ao_samples"),
+        ];
+        for (text, key) in cases {
+            assert_eq!(invalid_key(text), key, "{text}");
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use image::{ImageBuffer, Pixel};
+
+/// Mean difference per pixel by which the edges of a map may differ more than the pixels next to
+/// them before it is considered not to tile.
+pub const SEAM_TOLERANCE: f32 = 0.02;
+
+/// How much more a map changes across its edges than between the pixels next to them, which it
+/// would not if it tiled.
+pub struct SeamReport {
+    /// Between the right edge and the left one
+    pub horizontal: f32,
+    /// Between the top edge and the bottom one
+    pub vertical: f32,
+}
+
+impl SeamReport {
+    /// Compares the difference between opposite edges of `image` to the differences between
+    /// each edge and the pixels next to it, along every row and column.
+    pub fn measure<P: Pixel>(
+        image: &ImageBuffer<P, Vec<P::Subpixel>>,
+        distance: impl Fn(&P, &P) -> f32,
+    ) -> Self {
+        let (width, height) = image.dimensions();
+        // Excess of the step from the last pixel of a line to the first one over the steps
+        // next to it.
+        let excess = |length: u32, count: u32, step: &dyn Fn(u32, u32, u32) -> f32| {
+            if length < 3 || count == 0 {
+                return 0.;
+            }
+            let sum = (0..count)
+                .map(|line| {
+                    let seam = step(line, length - 1, 0);
+                    let inside = 0.5 * (step(line, length - 2, length - 1) + step(line, 0, 1));
+                    seam - inside
+                })
+                .sum::<f32>();
+            (sum / count as f32).max(0.)
+        };
+
+        Self {
+            horizontal: excess(width, height, &|y, a, b| {
+                distance(image.get_pixel(a, y), image.get_pixel(b, y))
+            }),
+            vertical: excess(height, width, &|x, a, b| {
+                distance(image.get_pixel(x, a), image.get_pixel(x, b))
+            }),
+        }
+    }
+
+    pub fn is_seamless(&self) -> bool {
+        self.horizontal <= SEAM_TOLERANCE && self.vertical <= SEAM_TOLERANCE
+    }
+}
+
+impl fmt::Display for SeamReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "seam error x {:.4}, y {:.4}",
+            self.horizontal, self.vertical
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Luma;
+
+    use super::*;
+
+    fn report(width: u32, value: impl Fn(u32, u32) -> u8) -> SeamReport {
+        let image = ImageBuffer::from_fn(width, 8, |x, y| Luma([value(x, y)]));
+        SeamReport::measure(&image, |a: &Luma<u8>, b| {
+            a.0[0].abs_diff(b.0[0]) as f32 / 255.
+        })
+    }
+
+    #[test]
+    fn periodic_maps_are_seamless() {
+        let stripes = report(12, |x, _| if (x + 1) % 4 < 2 { 0 } else { 255 });
+        assert!(stripes.is_seamless(), "{stripes}");
+        let wave = report(64, |x, _| {
+            (128. + 100. * (std::f32::consts::TAU * x as f32 / 64.).sin()) as u8
+        });
+        assert!(wave.is_seamless(), "{wave}");
+    }
+
+    #[test]
+    fn broken_maps_are_not() {
+        let ramp = report(12, |x, _| (x * 20) as u8);
+        assert!(!ramp.is_seamless(), "{ramp}");
+        assert_eq!(ramp.vertical, 0.);
+    }
+}
@@ -0,0 +1,338 @@
+use clap::ValueEnum;
+use nalgebra::{Matrix4, Point2, Vector2, Vector4};
+use serde::Deserialize;
+
+use crate::{line::DistanceResult, wire::WireNode};
+
+/// Interpolation of the nodes of a wire.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Curve {
+    /// Straight segments between consecutive nodes
+    #[default]
+    Polyline,
+    /// Cubic through every node but the first and the last, which only steer the ends
+    CatmullRom,
+    /// Cubic Bezier segments, every third node lying on the curve and the two between being
+    /// its handles
+    Bezier,
+    /// Uniform cubic B-spline, smoother than the others but only passing near its nodes
+    BSpline,
+}
+
+impl Curve {
+    /// Number of segments of a wire of `nodes` nodes.
+    pub fn segment_count(self, nodes: usize) -> usize {
+        match self {
+            Curve::Polyline => nodes.saturating_sub(1),
+            Curve::CatmullRom | Curve::BSpline => nodes.saturating_sub(3),
+            Curve::Bezier => nodes.saturating_sub(1) / 3,
+        }
+    }
+
+    /// Nodes controlling `segment`.
+    pub fn controls(self, segment: usize) -> std::ops::Range<usize> {
+        match self {
+            Curve::Polyline => segment..segment + 2,
+            Curve::CatmullRom | Curve::BSpline => segment..segment + 4,
+            Curve::Bezier => 3 * segment..3 * segment + 4,
+        }
+    }
+
+    /// Node `segment` starts from, which gives it its id.
+    pub fn start_node(self, segment: usize) -> usize {
+        match self {
+            Curve::Polyline => segment,
+            Curve::CatmullRom | Curve::BSpline => segment + 1,
+            Curve::Bezier => 3 * segment,
+        }
+    }
+
+    /// Coefficients of the powers of t, by row, in terms of the controls of a segment.
+    fn basis(self) -> Matrix4<f32> {
+        match self {
+            Curve::Polyline => Matrix4::new(
+                1., 0., 0., 0., //
+                -1., 1., 0., 0., //
+                0., 0., 0., 0., //
+                0., 0., 0., 0.,
+            ),
+            Curve::CatmullRom => {
+                Matrix4::new(
+                    0., 2., 0., 0., //
+                    -1., 0., 1., 0., //
+                    2., -5., 4., -1., //
+                    -1., 3., -3., 1.,
+                ) / 2.
+            }
+            Curve::Bezier => Matrix4::new(
+                1., 0., 0., 0., //
+                -3., 3., 0., 0., //
+                3., -6., 3., 0., //
+                -1., 3., -3., 1.,
+            ),
+            Curve::BSpline => {
+                Matrix4::new(
+                    1., 4., 1., 0., //
+                    -3., 0., 3., 0., //
+                    3., -6., 3., 0., //
+                    -1., 3., -3., 1.,
+                ) / 6.
+            }
+        }
+    }
+
+    /// Control nodes of a wire of this curve passing through or near `samples`, the first and
+    /// last samples lying beyond the ends of the wire and only setting its direction there.
+    pub fn fit(self, mut samples: Vec<WireNode>) -> Vec<WireNode> {
+        match self {
+            Curve::Polyline => {
+                samples.pop();
+                if !samples.is_empty() {
+                    samples.remove(0);
+                }
+                samples
+            }
+            Curve::CatmullRom | Curve::BSpline => samples,
+            // Handles along the tangents of the Catmull-Rom spline through the samples.
+            Curve::Bezier => {
+                let n = samples.len();
+                let mut nodes = Vec::with_capacity(3 * n);
+                for i in 1..n.saturating_sub(2) {
+                    let (before, a, b, after) = (
+                        &samples[i - 1],
+                        &samples[i],
+                        &samples[i + 1],
+                        &samples[i + 2],
+                    );
+                    nodes.push(a.clone());
+                    nodes.push(WireNode {
+                        position: a.position + (b.position - before.position) / 6.,
+                        width: a.width + (b.width - before.width) / 6.,
+                        ..a.clone()
+                    });
+                    nodes.push(WireNode {
+                        position: b.position - (after.position - a.position) / 6.,
+                        width: b.width - (after.width - a.width) / 6.,
+                        ..a.clone()
+                    });
+                }
+                if n >= 4 {
+                    nodes.push(samples[n - 2].clone());
+                }
+                nodes
+            }
+        }
+    }
+}
+
+/// Cubic segment of a curve, whose points carry the position along x, y and z and the half
+/// width along w.
+pub struct Spline {
+    /// Coefficients of the powers of t
+    coefficients: [Vector4<f32>; 4],
+}
+
+impl Spline {
+    pub fn new(curve: Curve, controls: &[Vector4<f32>]) -> Self {
+        let basis = curve.basis();
+        let coefficients = std::array::from_fn(|k| {
+            controls
+                .iter()
+                .enumerate()
+                .map(|(i, c)| basis[(k, i)] * c)
+                .sum()
+        });
+        Self { coefficients }
+    }
+
+    pub fn point(&self, t: f32) -> Vector4<f32> {
+        let [c0, c1, c2, c3] = &self.coefficients;
+        c0 + t * (c1 + t * (c2 + t * c3))
+    }
+
+    /// Derivative with respect to t.
+    pub fn derivative(&self, t: f32) -> Vector4<f32> {
+        let [_, c1, c2, c3] = &self.coefficients;
+        c1 + t * (2. * c2 + 3. * t * c3)
+    }
+
+    fn second_derivative(&self, t: f32) -> Vector4<f32> {
+        let [_, _, c2, c3] = &self.coefficients;
+        2. * c2 + 6. * t * c3
+    }
+
+    /// Control points of the segment in the Bezier basis, whose bounding box contains it.
+    pub fn bezier(&self) -> [Vector4<f32>; 4] {
+        let [c0, c1, c2, c3] = &self.coefficients;
+        [
+            *c0,
+            c0 + c1 / 3.,
+            c0 + (2. * c1 + c2) / 3.,
+            c0 + c1 + c2 + c3,
+        ]
+    }
+
+    /// Closest point of the segment to `point` across the plane, found among regularly spaced
+    /// positions then refined with Newton's method, damped when it overshoots.
+    pub fn distance_to_point(&self, point: Point2<f32>) -> DistanceResult {
+        const STEPS: usize = 8;
+        let offset = |t: f32| -> Vector2<f32> { self.point(t).xy() - point.coords };
+        let mut t = (0..=STEPS)
+            .map(|i| i as f32 / STEPS as f32)
+            .min_by(|a, b| {
+                offset(*a)
+                    .norm_squared()
+                    .total_cmp(&offset(*b).norm_squared())
+            })
+            .unwrap_or(0.);
+        let mut d2 = offset(t).norm_squared();
+        for _ in 0..4 {
+            let o = offset(t);
+            let d1 = self.derivative(t).xy();
+            let slope = o.dot(&d1);
+            let curvature = d1.norm_squared() + o.dot(&self.second_derivative(t).xy());
+            if curvature <= 0. {
+                break;
+            }
+            // Steps overshooting the closest point, as near the centre of a bend, are halved.
+            let mut step = slope / curvature;
+            let mut better = None;
+            for _ in 0..4 {
+                let next = (t - step).clamp(0., 1.);
+                let next_d2 = offset(next).norm_squared();
+                if next_d2 < d2 {
+                    better = Some((next, next_d2));
+                    break;
+                }
+                step /= 2.;
+            }
+            let Some(better) = better else {
+                break;
+            };
+            (t, d2) = better;
+        }
+
+        // Beyond an end, the point lies ahead of the curve there.
+        let along = -offset(t).dot(&self.derivative(t).xy());
+        let cap = (t == 0. && along < 0.) || (t == 1. && along > 0.);
+        let d = d2.sqrt();
+        if cap {
+            DistanceResult::Caps { t, d }
+        } else {
+            DistanceResult::Full { t, d }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [Curve; 4] = [
+        Curve::Polyline,
+        Curve::CatmullRom,
+        Curve::Bezier,
+        Curve::BSpline,
+    ];
+
+    /// Controls of a segment bending one way then the other, as a yarn over and under.
+    fn controls(curve: Curve) -> Vec<Vector4<f32>> {
+        let nodes = [
+            Vector4::new(0., 0., 0., 0.1),
+            Vector4::new(1., 0.8, 0.3, 0.12),
+            Vector4::new(2., -0.6, -0.2, 0.1),
+            Vector4::new(3., 0.2, 0.1, 0.08),
+        ];
+        nodes[..curve.controls(0).len()].to_vec()
+    }
+
+    fn assert_close(a: Vector4<f32>, b: Vector4<f32>, tolerance: f32) {
+        assert!((a - b).norm() < tolerance, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn segments_join_their_nodes() {
+        let nodes = controls(Curve::CatmullRom);
+        let catmull_rom = Spline::new(Curve::CatmullRom, &nodes);
+        assert_close(catmull_rom.point(0.), nodes[1], 1e-6);
+        assert_close(catmull_rom.point(1.), nodes[2], 1e-6);
+        let bezier = Spline::new(Curve::Bezier, &nodes);
+        assert_close(bezier.point(0.), nodes[0], 1e-6);
+        assert_close(bezier.point(1.), nodes[3], 1e-6);
+        let b_spline = Spline::new(Curve::BSpline, &nodes);
+        assert_close(
+            b_spline.point(0.),
+            (nodes[0] + 4. * nodes[1] + nodes[2]) / 6.,
+            1e-6,
+        );
+        let polyline = Spline::new(Curve::Polyline, &nodes[..2]);
+        assert_close(polyline.point(0.25), nodes[0].lerp(&nodes[1], 0.25), 1e-6);
+    }
+
+    #[test]
+    fn derivatives_follow_the_points() {
+        let h = 1e-3;
+        for curve in CURVES {
+            let spline = Spline::new(curve, &controls(curve));
+            for i in 1..10 {
+                let t = i as f32 / 10.;
+                let difference = (spline.point(t + h) - spline.point(t - h)) / (2. * h);
+                assert_close(spline.derivative(t), difference, 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn bezier_controls_give_the_same_segment() {
+        for curve in CURVES {
+            let spline = Spline::new(curve, &controls(curve));
+            let bezier = Spline::new(Curve::Bezier, &spline.bezier());
+            for i in 0..=10 {
+                let t = i as f32 / 10.;
+                assert_close(bezier.point(t), spline.point(t), 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn closest_points_match_a_dense_search() {
+        for curve in CURVES {
+            let spline = Spline::new(curve, &controls(curve));
+            // Points within a yarn width of the curve, on both sides.
+            for i in 0..=20 {
+                let t = i as f32 / 20.;
+                let tangent = spline.derivative(t).xy().normalize();
+                let normal = Vector2::new(-tangent.y, tangent.x);
+                for offset in [-0.15, -0.05, 0.05, 0.15] {
+                    let point = Point2::from(spline.point(t).xy() + offset * normal);
+                    let d = match spline.distance_to_point(point) {
+                        DistanceResult::Caps { d, .. } | DistanceResult::Full { d, .. } => d,
+                    };
+                    let closest = (0..=2_000)
+                        .map(|i| (spline.point(i as f32 / 2_000.).xy() - point.coords).norm())
+                        .fold(f32::INFINITY, f32::min);
+                    assert!(
+                        (d - closest).abs() < 1e-4,
+                        "{curve:?} at {point}: {d} instead of {closest}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn points_beyond_the_ends_are_caps() {
+        let nodes = [Vector4::new(0., 0., 0., 0.1), Vector4::new(1., 0., 0., 0.1)];
+        let line = Spline::new(Curve::Polyline, &nodes);
+        let result = |x, y| line.distance_to_point(Point2::new(x, y));
+        assert!(matches!(result(-0.5, 0.1), DistanceResult::Caps { t, .. } if t == 0.));
+        assert!(matches!(result(1.5, 0.1), DistanceResult::Caps { t, .. } if t == 1.));
+        match result(0.25, 0.2) {
+            DistanceResult::Full { t, d } => {
+                assert!((t - 0.25).abs() < 1e-5 && (d - 0.2).abs() < 1e-5)
+            }
+            DistanceResult::Caps { .. } => panic!("the point lies along the segment"),
+        }
+    }
+}
@@ -1,8 +1,23 @@
 use std::path::Path;
 
-use image::{DynamicImage, ImageBuffer, Rgb, Rgb32FImage};
+use image::{ImageBuffer, Rgb, Rgb32FImage};
 use nalgebra::*;
 
+use crate::{
+    error::{Error, Result},
+    output::OutputFormat,
+    seam::SeamReport,
+};
+
+/// sRGB encoding of a linear colour channel.
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
 pub struct Texture {
     pub image: Rgb32FImage,
     pub extent: Vector2<f32>,
@@ -23,6 +38,22 @@ impl Texture {
     pub fn size(&self) -> Vector2<u32> {
         Vector2::new(self.image.width(), self.image.height())
     }
+
+    /// Seams of the texture, channels clamped to [0, 1] in PNG files as when saved.
+    pub fn seam_report(&self, format: OutputFormat) -> SeamReport {
+        let clamp = |c: f32| match format {
+            _ if !c.is_finite() => 0.,
+            OutputFormat::Png8 | OutputFormat::Png16 => c.clamp(0., 1.),
+            OutputFormat::Exr => c,
+        };
+        SeamReport::measure(&self.image, |a, b| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .map(|(a, b)| (clamp(*a) - clamp(*b)).abs())
+                .sum::<f32>()
+                / 3.
+        })
+    }
 }
 
 impl TextureU8 {
@@ -36,8 +67,13 @@ impl TextureU8 {
         Vector2::new(self.image.width(), self.image.height())
     }
 
-    pub fn save<P: AsRef<Path>>(self, path: P) {
-        let dynamic_image = DynamicImage::from(self.image);
-        dynamic_image.into_rgb8().save(path).unwrap();
+    /// Seams of the texture, counting the pixels whose value changes.
+    pub fn seam_report(&self) -> SeamReport {
+        SeamReport::measure(&self.image, |a, b| if a == b { 0. } else { 1. })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.image.save(path).map_err(|e| Error::image(path, e))
     }
 }
@@ -0,0 +1,276 @@
+use nalgebra::{Point3, RealField, Vector2, Vector3};
+use noise::{NoiseFn, Perlin};
+
+use crate::{
+    World,
+    draft::Draft,
+    profile::{Profile, ProfileParameters},
+    spline::Curve,
+    wire::{Material, SimpleColoredMaterial, Wire, WireNode},
+};
+
+pub struct NoiseParameters {
+    pub seed: u32,
+    /// Number of noise periods across the extent
+    pub scale: f64,
+    /// Maximum displacement of a node in the plane
+    pub strength: f64,
+}
+
+pub struct YarnParameters {
+    /// Mean half width of a thread
+    pub width: f32,
+    /// Amplitude of the noise applied to the width
+    pub width_variation: f32,
+    pub color: Vector3<f32>,
+    pub roughness: f32,
+    pub roughness_variation: f32,
+    pub sheen_color: Vector3<f32>,
+    pub sheen_roughness: f32,
+    /// Fraction of the light passing straight through `transmission_thickness` of yarn
+    pub transmission: f32,
+    /// In world units, the diameter of the yarn when `None`
+    pub transmission_thickness: Option<f32>,
+    pub profile: ProfileParameters,
+    /// Fraction of the height of the yarn lost where it is pressed against the yarns it
+    /// crosses
+    pub crossing_flattening: f32,
+}
+
+pub struct WeaveParameters {
+    /// Number of warp (x) and weft (y) threads, multiples of the draft repeat
+    pub count: Vector2<u32>,
+    /// Number of nodes between two consecutive crossings
+    pub resolution: u32,
+    /// Interpolation of the nodes, smooth curves needing fewer of them
+    pub curve: Curve,
+    /// Interlacement of the warp and weft threads
+    pub draft: Draft,
+    /// Amplitude of the z oscillation of the threads
+    pub crimp: f32,
+    pub noise: NoiseParameters,
+    pub warp: YarnParameters,
+    pub weft: YarnParameters,
+    /// Size of the tile in millimetres
+    pub tile_mm: f32,
+}
+
+impl Default for NoiseParameters {
+    fn default() -> Self {
+        Self {
+            seed: 1234,
+            scale: 10.,
+            strength: 0.02,
+        }
+    }
+}
+
+impl Default for YarnParameters {
+    fn default() -> Self {
+        Self {
+            width: 0.018,
+            width_variation: 0.001,
+            color: Vector3::new(0.8, 0.8, 0.8),
+            roughness: 0.7,
+            roughness_variation: 0.2,
+            sheen_color: Vector3::new(0.3, 0.3, 0.3),
+            sheen_roughness: 0.5,
+            transmission: 0.2,
+            transmission_thickness: None,
+            profile: ProfileParameters::default(),
+            crossing_flattening: 0.,
+        }
+    }
+}
+
+impl YarnParameters {
+    pub fn material(&self) -> Box<dyn Material> {
+        Box::new(SimpleColoredMaterial {
+            color: self.color,
+            roughness: self.roughness,
+            roughness_variation: self.roughness_variation,
+            sheen_color: self.sheen_color,
+            sheen_roughness: self.sheen_roughness,
+            transmission: self.transmission,
+            transmission_thickness: self.transmission_thickness.unwrap_or(2. * self.width),
+        })
+    }
+
+    pub fn profile(&self) -> Box<dyn Profile> {
+        self.profile.profile()
+    }
+
+    /// Scale of the height of the yarn, `crossing` going from 0 between two crossings to 1 on
+    /// them.
+    pub fn height_scale(&self, crossing: f32) -> f32 {
+        1. - self.crossing_flattening * crossing
+    }
+}
+
+impl Default for WeaveParameters {
+    fn default() -> Self {
+        Self {
+            count: Vector2::new(24, 24),
+            resolution: 4,
+            curve: Curve::Polyline,
+            draft: Draft::plain(),
+            crimp: 0.01,
+            noise: NoiseParameters::default(),
+            warp: YarnParameters::default(),
+            weft: YarnParameters::default(),
+            tile_mm: 10.,
+        }
+    }
+}
+
+impl WeaveParameters {
+    /// Replaces the draft, rounding the thread counts up to a whole number of repeats.
+    pub fn set_draft(&mut self, draft: Draft) {
+        let (ends, picks) = draft.repeat();
+        self.count.x = self.count.x.next_multiple_of(ends as u32);
+        self.count.y = self.count.y.next_multiple_of(picks as u32);
+        self.draft = draft;
+    }
+}
+
+pub fn periodic_noise_2d(perlin: &Perlin, x: f64, y: f64, period: f64) -> f64 {
+    let nx = (x / period) * std::f64::consts::TAU; // TAU = 2π
+    let ny = (y / period) * std::f64::consts::TAU;
+
+    let x1 = nx.cos();
+    let x2 = nx.sin();
+    let y1 = ny.cos();
+    let y2 = ny.sin();
+
+    perlin.get([x1, x2, y1, y2])
+}
+
+/// Height of a thread between two crossings, `t` going from 0 at the first crossing to 1 at
+/// the next one.
+fn crimp_height(crimp: f32, over_before: bool, over_after: bool, t: f32) -> f32 {
+    let z = |over: bool| if over { crimp } else { -crimp };
+    let s = 0.5 - 0.5 * (f32::pi() * t).cos();
+    z(over_before) * (1. - s) + z(over_after) * s
+}
+
+pub fn generate_tissage(world: &mut World, parameters: &WeaveParameters) {
+    let count_x = parameters.count.x;
+    let count_y = parameters.count.y;
+    let res = parameters.resolution;
+    let draft = &parameters.draft;
+
+    let perlin_x: Perlin = Perlin::new(parameters.noise.seed);
+    let perlin_y: Perlin = Perlin::new(parameters.noise.seed.wrapping_add(42));
+
+    let perlin_scale: f64 = parameters.noise.scale;
+    let perlin_strength: f64 = parameters.noise.strength;
+
+    let scale_x = 1. / (count_x as f32);
+    let scale_y = 1. / (count_y as f32);
+
+    let noise = |yarn: &YarnParameters, x_pos: f32, y_pos: f32| {
+        let sample = |perlin: &Perlin| {
+            periodic_noise_2d(
+                perlin,
+                perlin_scale * x_pos as f64,
+                perlin_scale * y_pos as f64,
+                perlin_scale,
+            )
+        };
+        let n = Vector2::new(sample(&perlin_x), sample(&perlin_y));
+        let offset = perlin_strength * n;
+        let w = yarn.width + yarn.width_variation * n.x as f32;
+        (offset.map(|o| o as f32), w)
+    };
+
+    // Every crossing gets two ids, the even one for the warp and the odd one for the weft.
+    let crossing_index =
+        |end: u32, pick: u32| 2 * ((pick % count_y) * count_x + end % count_x) as usize;
+
+    world.set_period(Some(Vector2::new(1., 1.)));
+    world.millimetres_per_unit = Some(parameters.tile_mm);
+
+    // Every thread covers exactly one tile, the last node closing it on the first one. The
+    // nodes before the first crossing and after the last one, in the neighbouring tiles, only
+    // steer the ends of the curve.
+    let curve = parameters.curve;
+    for x in 0..count_x {
+        let node = |y: i32, i: u32| {
+            let pick = y.rem_euclid(count_y as i32) as u32;
+            let over_before = draft.warp_over(x, pick);
+            let over_after = draft.warp_over(x, pick + 1);
+            // scale_y => step length
+            // scale_y/res => micro_step length
+            let t = i as f32 / (res as f32);
+            let x_pos = x as f32 * scale_x;
+            let y_pos = (y as f32 + t) * scale_y;
+
+            let (offset, w) = noise(&parameters.warp, x_pos, y_pos);
+            let pick = if t < 0.5 { pick } else { pick + 1 };
+            let crossing = 0.5 + 0.5 * (f32::two_pi() * t).cos();
+            WireNode {
+                height_scale: parameters.warp.height_scale(crossing),
+                ..WireNode::new(
+                    crossing_index(x, pick),
+                    Point3::new(
+                        x_pos + offset.x,
+                        y_pos + offset.y,
+                        crimp_height(parameters.crimp, over_before, over_after, t),
+                    ),
+                    w,
+                )
+            }
+        };
+        let mut nodes: Vec<WireNode> = vec![node(-1, res - 1)];
+        for y in 0..=count_y {
+            let last = if y == count_y { 1 } else { res };
+            nodes.extend((0..last).map(|i| node(y as i32, i)));
+        }
+        nodes.push(node(count_y as i32, 1));
+        world.wires_mut().push(Wire {
+            curve,
+            profile: parameters.warp.profile(),
+            ..Wire::new_from_nodes_with_material(curve.fit(nodes), true, parameters.warp.material())
+        });
+    }
+
+    for y in 0..count_y {
+        let node = |x: i32, i: u32| {
+            let end = x.rem_euclid(count_x as i32) as u32;
+            let under_before = !draft.warp_over(end, y);
+            let under_after = !draft.warp_over(end + 1, y);
+            // scale_x => step length
+            // scale_x/res => micro_step length
+            let t = i as f32 / (res as f32);
+            let x_pos = (x as f32 + t) * scale_x;
+            let y_pos = y as f32 * scale_y;
+
+            let (offset, w) = noise(&parameters.weft, x_pos, y_pos);
+            let end = if t < 0.5 { end } else { end + 1 };
+            let crossing = 0.5 + 0.5 * (f32::two_pi() * t).cos();
+            WireNode {
+                height_scale: parameters.weft.height_scale(crossing),
+                ..WireNode::new(
+                    crossing_index(end, y) + 1,
+                    Point3::new(
+                        x_pos + offset.x,
+                        y_pos + offset.y,
+                        crimp_height(parameters.crimp, under_before, under_after, t),
+                    ),
+                    w,
+                )
+            }
+        };
+        let mut nodes: Vec<WireNode> = vec![node(-1, res - 1)];
+        for x in 0..=count_x {
+            let last = if x == count_x { 1 } else { res };
+            nodes.extend((0..last).map(|i| node(x as i32, i)));
+        }
+        nodes.push(node(count_x as i32, 1));
+        world.wires_mut().push(Wire {
+            curve,
+            profile: parameters.weft.profile(),
+            ..Wire::new_from_nodes_with_material(curve.fit(nodes), true, parameters.weft.material())
+        });
+    }
+}
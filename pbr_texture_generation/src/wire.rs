@@ -1,17 +1,36 @@
-use core::f32;
+use nalgebra::{Point2, Point3, Vector2, Vector3, Vector4};
 
-use nalgebra::{Point2, Point3, Vector2, Vector3};
-
-use crate::{drawable::Drawable, line::Line};
+use crate::{
+    drawable::{Drawable, Hit, SurfaceSample},
+    line::Line,
+    profile::{CircleProfile, Profile},
+    spline::{Curve, Spline},
+};
 
+#[derive(Clone)]
 pub struct WireNode {
     pub index: usize,
     pub position: Point3<f32>,
     pub width: f32,
+    /// Height of the cross-section relative to the one of the profile, below 1 where the yarn
+    /// is pressed flat
+    pub height_scale: f32,
 }
 
 pub trait Material: Sync + Send {
     fn get_color(&self) -> Vector3<f32>;
+    /// Roughness of the fibres lying flat on top of the yarn
+    fn get_roughness(&self) -> f32;
+    /// Roughness added where the fibres turn away from the surface
+    fn get_roughness_variation(&self) -> f32;
+    /// Colour of the rim light scattered by the fibres of the most exposed parts of the yarn
+    fn get_sheen_color(&self) -> Vector3<f32>;
+    /// Spread of the sheen, from the tight rim of smooth filaments to the haze of fuzzy fibres
+    fn get_sheen_roughness(&self) -> f32;
+    /// Fraction of the light passing straight through `get_transmission_thickness` of yarn
+    fn get_transmission(&self) -> f32;
+    /// Thickness of yarn the transmission is measured through, in world units
+    fn get_transmission_thickness(&self) -> f32;
 }
 
 struct RopeMaterial;
@@ -20,50 +39,96 @@ impl Material for RopeMaterial {
     fn get_color(&self) -> Vector3<f32> {
         Vector3::new(0., 0., 0.)
     }
+
+    fn get_roughness(&self) -> f32 {
+        0.7
+    }
+
+    fn get_roughness_variation(&self) -> f32 {
+        0.2
+    }
+
+    fn get_sheen_color(&self) -> Vector3<f32> {
+        Vector3::new(0., 0., 0.)
+    }
+
+    fn get_sheen_roughness(&self) -> f32 {
+        1.
+    }
+
+    fn get_transmission(&self) -> f32 {
+        0.
+    }
+
+    fn get_transmission_thickness(&self) -> f32 {
+        1.
+    }
 }
 
-pub trait Profile: Sync + Send {
-    fn get_height(&self, x_normalized: f32) -> f32;
-    fn get_normal(&self, line: &Line, w1: f32, w2: f32, t: f32, x_normalized: f32) -> Vector3<f32>;
+pub struct SimpleColoredMaterial {
+    pub color: Vector3<f32>,
+    pub roughness: f32,
+    pub roughness_variation: f32,
+    pub sheen_color: Vector3<f32>,
+    pub sheen_roughness: f32,
+    pub transmission: f32,
+    pub transmission_thickness: f32,
 }
 
-struct CircleProfile;
-impl Profile for CircleProfile {
-    fn get_height(&self, x_normalized: f32) -> f32 {
-        return x_normalized.cos();
-    }
-
-    fn get_normal(
-        &self,
-        line: &Line,
-        w1: f32,
-        w2: f32,
-        _t: f32,
-        x_normalized: f32,
-    ) -> Vector3<f32> {
-        let v_line: Vector2<f32> = line.end - line.start;
-        let line_length = v_line.norm();
-        let scale_angle = ((w2 - w1) / line_length).atan();
-
-        let v_front = Vector3::new(v_line.x, v_line.y, 0.).normalize();
-        let v_top = Vector3::new(0., 0., 1.);
-        let v_right = v_front.cross(&v_top).normalize();
-
-        let x_normalized = f32::consts::PI * x_normalized;
-
-        return (scale_angle.cos() * v_top
-            + scale_angle.sin() * v_front
-            + x_normalized.sin() * v_right)
-            .normalize();
+impl Material for SimpleColoredMaterial {
+    fn get_color(&self) -> Vector3<f32> {
+        self.color
+    }
+
+    fn get_roughness(&self) -> f32 {
+        self.roughness
+    }
+
+    fn get_roughness_variation(&self) -> f32 {
+        self.roughness_variation
+    }
+
+    fn get_sheen_color(&self) -> Vector3<f32> {
+        self.sheen_color
+    }
+
+    fn get_sheen_roughness(&self) -> f32 {
+        self.sheen_roughness
+    }
+
+    fn get_transmission(&self) -> f32 {
+        self.transmission
+    }
+
+    fn get_transmission_thickness(&self) -> f32 {
+        self.transmission_thickness
     }
 }
 
 pub struct Wire {
     pub nodes: Vec<WireNode>,
-    #[allow(unused)]
     pub material: Box<dyn Material>,
     pub profile: Box<dyn Profile>,
     pub caps: bool,
+    /// Interpolation of the nodes
+    pub curve: Curve,
+}
+
+/// Axis of a segment at a position along it.
+struct Axis {
+    origin: Point3<f32>,
+    /// Unit tangent
+    direction: Vector3<f32>,
+    width: f32,
+}
+
+impl Axis {
+    /// Horizontal unit vector to the right of the axis.
+    fn right(&self) -> Vector3<f32> {
+        Vector3::new(self.direction.y, -self.direction.x, 0.)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(Vector3::x())
+    }
 }
 
 impl WireNode {
@@ -72,12 +137,12 @@ impl WireNode {
             index,
             position,
             width,
+            height_scale: 1.,
         }
     }
 }
 
 impl Wire {
-    #[allow(unused)]
     pub fn new(
         index_begin: usize,
         begin: Point3<f32>,
@@ -96,6 +161,7 @@ impl Wire {
             material: Box::new(RopeMaterial),
             profile: Box::new(CircleProfile),
             caps,
+            curve: Curve::Polyline,
         }
     }
 
@@ -105,6 +171,7 @@ impl Wire {
             material: Box::new(RopeMaterial),
             profile: Box::new(CircleProfile),
             caps,
+            curve: Curve::Polyline,
         }
     }
 
@@ -118,6 +185,7 @@ impl Wire {
             material,
             profile: Box::new(CircleProfile),
             caps,
+            curve: Curve::Polyline,
         }
     }
 }
@@ -126,299 +194,316 @@ fn lerp(a: f32, b: f32, s: f32) -> f32 {
     b * s + a * (1. - s)
 }
 
-impl Drawable for Wire {
-    fn get_height(&self, point: Point2<f32>) -> f32 {
-        let mut best_cap_z: Option<f32> = None;
-        let mut best_hit_z: Option<f32> = None;
-        for window in self.nodes.windows(2) {
-            match &window {
-                &[a, b] => {
-                    let line = Line::new(a.position.xy(), b.position.xy());
-                    let wm = a.width.max(b.width);
-                    let minimum = Vector2::new(
-                        a.position.x.min(b.position.x) - wm,
-                        a.position.y.min(b.position.y) - wm,
-                    );
-                    let maximum = Vector2::new(
-                        a.position.x.max(b.position.x) + wm,
-                        a.position.y.max(b.position.y) + wm,
-                    );
-                    if point.x < minimum.x
-                        || point.x > maximum.x
-                        || point.y < minimum.y
-                        || point.y > maximum.y
-                    {
-                        continue;
-                    }
-                    let w1 = a.width;
-                    let w2 = b.width;
-
-                    let z1 = a.position.z;
-                    let z2 = b.position.z;
-
-                    match line.distance_to_point(point) {
-                        crate::line::DistanceResult::Caps { t, d } => {
-                            let w = lerp(w1, w2, t);
-                            let z = lerp(z1, z2, t);
-                            if self.caps && d <= w {
-                                let new_z = z + w * self.profile.get_height(d / w);
-                                if let Some(old_best_cap_z) = best_cap_z {
-                                    if new_z > old_best_cap_z {
-                                        best_cap_z = Some(new_z);
-                                    }
-                                } else {
-                                    best_cap_z = Some(new_z);
-                                }
-                            }
-                        }
-                        crate::line::DistanceResult::Full { t, d } => {
-                            let w = lerp(w1, w2, t);
-                            let z = lerp(z1, z2, t);
-                            if d <= w {
-                                let new_z = z + w * self.profile.get_height(d / w);
-                                if let Some(old_best_hit_z) = best_hit_z {
-                                    if new_z > old_best_hit_z {
-                                        best_hit_z = Some(new_z);
-                                    }
-                                } else {
-                                    best_hit_z = Some(new_z);
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => (),
-            }
+impl Wire {
+    /// Fibres lying flat along the top of the yarn reflect sharply, those wrapping around its
+    /// flanks or diving under a crossing scatter the light.
+    fn roughness(&self, axis: &Axis, hit: &Hit) -> f32 {
+        let across = (hit.d / hit.width).powi(2);
+        let tilt = axis.direction.z.abs();
+        let scatter = 1. - (1. - across) * (1. - tilt);
+        (self.material.get_roughness() + self.material.get_roughness_variation() * scatter)
+            .clamp(0., 1.)
+    }
+
+    /// Light crossing the yarn at the hit is attenuated along the path between its top and its
+    /// bottom, thinning out towards the edges.
+    fn transmission(&self, hit: &Hit) -> f32 {
+        let path = 2.
+            * hit.width
+            * self.height_scale(hit.segment, hit.t)
+            * self.profile.get_height(hit.d / hit.width).max(0.);
+        self.material
+            .get_transmission()
+            .powf(path / self.material.get_transmission_thickness())
+    }
+
+    /// Cubic of a segment of a smooth wire.
+    fn spline(&self, segment: usize) -> Spline {
+        let nodes = &self.nodes[self.curve.controls(segment)];
+        let controls: [Vector4<f32>; 4] =
+            std::array::from_fn(|i| nodes[i].position.coords.push(nodes[i].width));
+        Spline::new(self.curve, &controls)
+    }
+
+    /// Position and half width along `segment`, as x, y, z and w, for `t` going from 0 to 1.
+    fn centerline(&self, segment: usize) -> impl Fn(f32) -> Vector4<f32> {
+        let spline = (self.curve != Curve::Polyline).then(|| self.spline(segment));
+        let [a, b] = [segment, segment + 1].map(|n| {
+            let node = &self.nodes[n.min(self.nodes.len() - 1)];
+            node.position.coords.push(node.width)
+        });
+        move |t| match &spline {
+            Some(spline) => spline.point(t),
+            None => a.lerp(&b, t),
+        }
+    }
+
+    /// Scale of the height of the cross-section along `segment`, easing between the nodes it
+    /// goes from and to.
+    fn height_scale(&self, segment: usize, t: f32) -> f32 {
+        let [a, b] = [segment, segment + 1]
+            .map(|s| self.nodes[self.curve.start_node(s).min(self.nodes.len() - 1)].height_scale);
+        lerp(a, b, t * t * (3. - 2. * t))
+    }
+
+    /// Axis of `segment` at `t`.
+    fn axis(&self, segment: usize, t: f32) -> Axis {
+        let (center, derivative) = if self.curve == Curve::Polyline {
+            let a = &self.nodes[segment];
+            let b = &self.nodes[segment + 1];
+            (
+                a.position
+                    .coords
+                    .push(a.width)
+                    .lerp(&b.position.coords.push(b.width), t),
+                b.position - a.position,
+            )
+        } else {
+            let spline = self.spline(segment);
+            (spline.point(t), spline.derivative(t).xyz())
+        };
+        Axis {
+            origin: Point3::from(center.xyz()),
+            direction: derivative
+                .try_normalize(f32::EPSILON)
+                .unwrap_or(Vector3::x()),
+            width: center.w,
         }
-        best_hit_z.unwrap_or(best_cap_z.unwrap_or(-f32::INFINITY))
-    }
-
-    fn get_height_with_id(&self, point: Point2<f32>) -> (f32, usize) {
-        let mut best_cap_z: Option<f32> = None;
-        let mut best_hit_z: Option<f32> = None;
-        let mut best_id: usize = 0;
-        for window in self.nodes.windows(2) {
-            match &window {
-                &[a, b] => {
-                    let line = Line::new(a.position.xy(), b.position.xy());
-                    let wm = a.width.max(b.width);
-                    let minimum = Vector2::new(
-                        a.position.x.min(b.position.x) - wm,
-                        a.position.y.min(b.position.y) - wm,
-                    );
-                    let maximum = Vector2::new(
-                        a.position.x.max(b.position.x) + wm,
-                        a.position.y.max(b.position.y) + wm,
-                    );
-                    if point.x < minimum.x
-                        || point.x > maximum.x
-                        || point.y < minimum.y
-                        || point.y > maximum.y
-                    {
-                        continue;
-                    }
-                    let w1 = a.width;
-                    let w2 = b.width;
-
-                    let z1 = a.position.z;
-                    let z2 = b.position.z;
-
-                    match line.distance_to_point(point) {
-                        crate::line::DistanceResult::Caps { t, d } => {
-                            let w = lerp(w1, w2, t);
-                            let z = lerp(z1, z2, t);
-                            if self.caps && d <= w {
-                                let new_z = z + w * self.profile.get_height(d / w);
-                                if let Some(old_best_cap_z) = best_cap_z {
-                                    if new_z > old_best_cap_z {
-                                        best_cap_z = Some(new_z);
-                                    }
-                                } else {
-                                    best_cap_z = Some(new_z);
-                                }
-                            }
-                        }
-                        crate::line::DistanceResult::Full { t, d } => {
-                            let w = lerp(w1, w2, t);
-                            let z = lerp(z1, z2, t);
-                            if d <= w {
-                                let new_z = z + w * self.profile.get_height(d / w);
-                                if let Some(old_best_hit_z) = best_hit_z {
-                                    if new_z > old_best_hit_z {
-                                        best_hit_z = Some(new_z);
-                                        best_id = a.index;
-                                    }
-                                } else {
-                                    best_hit_z = Some(new_z);
-                                    best_id = a.index;
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => (),
+    }
+
+    /// The yarn is the union of the profile turned around every point of its axis, spheres for
+    /// a circular profile sweeping a tube. Above a point, the surface is the top of the highest
+    /// of them, which lies up the slope of the axis from the closest point `t` across the plane.
+    /// Returns the position of its centre and its height.
+    fn top(&self, segment: usize, t: f32, point: Point2<f32>) -> (f32, f32) {
+        let centerline = self.centerline(segment);
+        // Solids the point is outside of sink steeply, so that the search settles on the
+        // others.
+        let height = |t: f32| {
+            let c = centerline(t);
+            let x = (point - Point2::from(c.xy())).norm() / c.w;
+            if x <= 1. {
+                c.z + c.w * self.height_scale(segment, t) * self.profile.get_height(x)
+            } else {
+                c.z - 100. * c.w * (x - 1.)
             }
+        };
+        // Only solids centred within a half width or so of the point reach it.
+        let (start, end) = (centerline(0.), centerline(1.));
+        let chord = (end.xy() - start.xy()).norm().max(f32::EPSILON);
+        let reach = 1.5 * start.w.max(end.w) / chord;
+        let (mut low, mut high) = ((t - reach).max(0.), (t + reach).min(1.));
+
+        // Golden section search.
+        const RATIO: f32 = 0.618_034;
+        let (mut t1, mut t2) = (high - RATIO * (high - low), low + RATIO * (high - low));
+        let (mut h1, mut h2) = (height(t1), height(t2));
+        for _ in 0..16 {
+            if h1 < h2 {
+                (low, t1, h1) = (t1, t2, h2);
+                t2 = low + RATIO * (high - low);
+                h2 = height(t2);
+            } else {
+                (high, t2, h2) = (t2, t1, h1);
+                t1 = high - RATIO * (high - low);
+                h1 = height(t1);
+            }
+        }
+        [(t, height(t)), (t1, h1), (t2, h2)]
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or((t, height(t)))
+    }
+
+    fn hit_segment(&self, segment: usize, point: Point2<f32>) -> Option<Hit> {
+        let (minimum, maximum) = self.segment_bounds(segment);
+        if point.x < minimum.x || point.x > maximum.x || point.y < minimum.y || point.y > maximum.y
+        {
+            return None;
+        }
+        let distance = if self.curve == Curve::Polyline {
+            let a = &self.nodes[segment];
+            let b = &self.nodes[segment + 1];
+            Line::new(a.position.xy(), b.position.xy()).distance_to_point(point)
+        } else {
+            self.spline(segment).distance_to_point(point)
+        };
+        let (t, d, cap) = match distance {
+            crate::line::DistanceResult::Caps { t, d } => (t, d, true),
+            crate::line::DistanceResult::Full { t, d } => (t, d, false),
+        };
+        if (cap && !self.caps) || d > self.centerline(segment)(t).w {
+            return None;
+        }
+
+        let (t, z) = self.top(segment, t, point);
+        let axis = self.axis(segment, t);
+        let offset = point - axis.origin.xy();
+        Some(Hit {
+            segment,
+            t,
+            d: offset.norm().min(axis.width),
+            side: axis.right().xy().dot(&offset).signum(),
+            width: axis.width,
+            z,
+            cap,
+        })
+    }
+}
+
+impl Drawable for Wire {
+    fn segment_count(&self) -> usize {
+        self.curve.segment_count(self.nodes.len())
+    }
+
+    fn segment_bounds(&self, segment: usize) -> (Point2<f32>, Point2<f32>) {
+        if self.curve != Curve::Polyline {
+            let controls = self.spline(segment).bezier();
+            let (min, max) = controls[1..]
+                .iter()
+                .fold((controls[0], controls[0]), |(min, max), c| {
+                    (min.inf(c), max.sup(c))
+                });
+            return (
+                Point2::from(min.xy()) - Vector2::repeat(max.w),
+                Point2::from(max.xy()) + Vector2::repeat(max.w),
+            );
         }
+        let a = &self.nodes[segment];
+        let b = &self.nodes[segment + 1];
+        let wm = a.width.max(b.width);
         (
-            best_hit_z.unwrap_or(best_cap_z.unwrap_or(-f32::INFINITY)),
-            best_id,
+            Point2::new(
+                a.position.x.min(b.position.x) - wm,
+                a.position.y.min(b.position.y) - wm,
+            ),
+            Point2::new(
+                a.position.x.max(b.position.x) + wm,
+                a.position.y.max(b.position.y) + wm,
+            ),
         )
     }
 
-    fn get_normal(&self, point: Point2<f32>) -> Vector3<f32> {
-        let mut best_cap_z: Option<f32> = None;
-        let mut best_hit_z: Option<f32> = None;
-        let mut best_cap_normal: Option<Vector3<f32>> = None;
-        let mut best_hit_normal: Option<Vector3<f32>> = None;
-
-        for window in self.nodes.windows(2) {
-            match &window {
-                &[a, b] => {
-                    let line = Line::new(a.position.xy(), b.position.xy());
-                    let w1 = a.width;
-                    let w2 = b.width;
-
-                    let z1 = a.position.z;
-                    let z2 = b.position.z;
-
-                    match line.distance_to_point(point) {
-                        crate::line::DistanceResult::Caps { t, d } => {
-                            let w = lerp(w1, w2, t);
-                            let z = lerp(z1, z2, t);
-
-                            let v_line: Vector2<f32> = line.end - line.start;
-
-                            let v_front = Vector3::new(v_line.x, v_line.y, 0.).normalize();
-                            let v_top = Vector3::new(0., 0., 1.);
-                            let v_right = v_front.cross(&v_top).normalize();
-
-                            let s = v_right.xy().dot(&(point - line.start)).signum();
-
-                            if d <= w {
-                                let new_normal =
-                                    self.profile.get_normal(&line, w1, w2, t, s * d / w);
-                                let new_z = z + w * self.profile.get_height(d / w);
-                                if let Some(old_best_cap_z) = best_cap_z {
-                                    if new_z > old_best_cap_z {
-                                        best_cap_z = Some(new_z);
-                                        best_cap_normal = Some(new_normal);
-                                    }
-                                } else {
-                                    best_cap_z = Some(new_z);
-                                    best_cap_normal = Some(new_normal);
-                                }
-                            }
-                        }
-                        crate::line::DistanceResult::Full { t, d } => {
-                            let w = lerp(w1, w2, t);
-                            let z = lerp(z1, z2, t);
-
-                            let v_line: Vector2<f32> = line.end - line.start;
-
-                            let v_front = Vector3::new(v_line.x, v_line.y, 0.).normalize();
-                            let v_top = Vector3::new(0., 0., 1.);
-                            let v_right = v_front.cross(&v_top).normalize();
-
-                            let s = v_right.xy().dot(&(point - line.start)).signum();
-
-                            if d <= w {
-                                let new_normal =
-                                    self.profile.get_normal(&line, w1, w2, t, s * d / w);
-                                let new_z = z + w * self.profile.get_height(d / w);
-                                if let Some(old_best_hit_z) = best_hit_z {
-                                    if new_z > old_best_hit_z {
-                                        best_hit_z = Some(new_z);
-                                        best_hit_normal = Some(new_normal);
-                                    }
-                                } else {
-                                    best_hit_z = Some(new_z);
-                                    best_hit_normal = Some(new_normal);
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => (),
-            }
+    /// Highest hit of the segments, points beyond their ends only hitting wires with caps.
+    fn hit(&self, point: Point2<f32>, segments: impl Iterator<Item = usize>) -> Option<Hit> {
+        // Every segment is part of the same solid, beyond its ends or not, whose top is the
+        // highest of them.
+        segments
+            .filter_map(|segment| self.hit_segment(segment, point))
+            .max_by(|a, b| a.z.total_cmp(&b.z))
+    }
+
+    fn sample(&self, point: Point2<f32>, hit: &Hit) -> SurfaceSample {
+        // The surface is the top of the solid centred at the hit, whose normal is the one of the
+        // profile turned towards the point, its slope scaled along with the height.
+        let axis = self.axis(hit.segment, hit.t);
+        let scale = self.height_scale(hit.segment, hit.t);
+        let offset = point - axis.origin.xy();
+        let across = Vector3::new(offset.x, offset.y, 0.)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| axis.right());
+        let section = self.profile.get_normal(hit.d / hit.width);
+        let normal = (scale * section.x * across + section.y * Vector3::z())
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(Vector3::z());
+        // Fibres facing up catch the full sheen, those on the flanks are hidden by the yarns
+        // around and look fuzzier.
+        let exposure = normal.z.clamp(0., 1.);
+        let sheen_roughness = self.material.get_sheen_roughness();
+        SurfaceSample {
+            height: hit.z,
+            normal,
+            tangent: axis
+                .direction
+                .xy()
+                .try_normalize(f32::EPSILON)
+                .unwrap_or(Vector2::new(1., 0.)),
+            albedo: self.material.get_color(),
+            roughness: self.roughness(&axis, hit),
+            sheen_color: exposure * self.material.get_sheen_color(),
+            sheen_roughness: lerp(1., sheen_roughness, 0.5 + 0.5 * exposure),
+            thickness: 2. * hit.width,
+            transmission: self.transmission(hit),
+            id: self.nodes[self.curve.start_node(hit.segment)].index,
+            coverage: 1.,
         }
-        best_hit_normal.unwrap_or(best_cap_normal.unwrap_or(Vector3::new(0., 0., 1.)))
-    }
-
-    fn get_albedo(&self, point: Point2<f32>) -> Vector3<f32> {
-        let mut best_cap_z: Option<f32> = None;
-        let mut best_hit_z: Option<f32> = None;
-        let mut best_cap_normal: Option<Vector3<f32>> = None;
-        let mut best_hit_normal: Option<Vector3<f32>> = None;
-
-        for window in self.nodes.windows(2) {
-            match &window {
-                &[a, b] => {
-                    let line = Line::new(a.position.xy(), b.position.xy());
-                    let wm = a.width.max(b.width);
-
-                    let minimum = Vector2::new(
-                        a.position.x.min(b.position.x) - wm,
-                        a.position.y.min(b.position.y) - wm,
-                    );
-                    let maximum = Vector2::new(
-                        a.position.x.max(b.position.x) + wm,
-                        a.position.y.max(b.position.y) + wm,
-                    );
-                    if point.x < minimum.x
-                        || point.x > maximum.x
-                        || point.y < minimum.y
-                        || point.y > maximum.y
-                    {
-                        continue;
-                    }
-
-                    let w1 = a.width;
-                    let w2 = b.width;
-                    let z1 = a.position.z;
-                    let z2 = b.position.z;
-
-                    match line.distance_to_point(point) {
-                        crate::line::DistanceResult::Caps { t, d } => {
-                            let w = lerp(w1, w2, t);
-                            let z = lerp(z1, z2, t);
-
-                            if d <= w {
-                                let new_normal = self.material.get_color();
-                                let new_z = z + w * self.profile.get_height(d / w);
-                                if let Some(old_best_cap_z) = best_cap_z {
-                                    if new_z > old_best_cap_z {
-                                        best_cap_z = Some(new_z);
-                                        best_cap_normal = Some(new_normal);
-                                    }
-                                } else {
-                                    best_cap_z = Some(new_z);
-                                    best_cap_normal = Some(new_normal);
-                                }
-                            }
-                        }
-                        crate::line::DistanceResult::Full { t, d } => {
-                            let w = lerp(w1, w2, t);
-                            let z = lerp(z1, z2, t);
-
-                            if d <= w {
-                                let new_normal = self.material.get_color();
-                                let new_z = z + w * self.profile.get_height(d / w);
-                                if let Some(old_best_hit_z) = best_hit_z {
-                                    if new_z > old_best_hit_z {
-                                        best_hit_z = Some(new_z);
-                                        best_hit_normal = Some(new_normal);
-                                    }
-                                } else {
-                                    best_hit_z = Some(new_z);
-                                    best_hit_normal = Some(new_normal);
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => (),
-            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wire of circular cross-section through `points`, `width` wide on either side.
+    fn wire(points: &[[f32; 3]], width: f32) -> Wire {
+        let nodes = points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| WireNode::new(i, Point3::from(*p), width))
+            .collect();
+        Wire::new_from_nodes(nodes, false)
+    }
+
+    fn hit(wire: &Wire, x: f32, y: f32) -> Option<Hit> {
+        wire.hit(Point2::new(x, y), 0..wire.segment_count())
+    }
+
+    #[test]
+    fn horizontal_tubes_are_round() {
+        let (z, w) = (0.3, 0.2);
+        let wire = wire(&[[-1., 0., z], [1., 0., z]], w);
+        for i in -9..=9 {
+            let x = i as f32 / 10.;
+            let hit = hit(&wire, 0.1, x * w).unwrap();
+            let expected = z + w * (1. - x * x).sqrt();
+            assert!((hit.z - expected).abs() < 1e-5, "{} != {expected}", hit.z);
+        }
+        assert!(hit(&wire, 0.1, 1.01 * w).is_none());
+    }
+
+    #[test]
+    fn normals_follow_the_heights() {
+        let wire = wire(&[[-1., -0.2, 0.], [1., 0.3, 0.6]], 0.25);
+        let height = |x: f32, y: f32| hit(&wire, x, y).unwrap().z;
+        let step = 1e-3;
+        for (x, y) in [(0., 0.), (0.1, 0.1), (-0.2, -0.25), (0.3, 0.), (0., 0.15)] {
+            let sample = wire.sample(Point2::new(x, y), &hit(&wire, x, y).unwrap());
+            let expected = Vector3::new(
+                -(height(x + step, y) - height(x - step, y)) / (2. * step),
+                -(height(x, y + step) - height(x, y - step)) / (2. * step),
+                1.,
+            )
+            .normalize();
+            assert!(
+                (sample.normal - expected).norm() < 1e-2,
+                "{:?} != {expected:?} at ({x}, {y})",
+                sample.normal
+            );
         }
-        best_hit_normal.unwrap_or(best_cap_normal.unwrap_or(Vector3::new(0., 0., 0.)))
+    }
+
+    #[test]
+    fn tops_move_up_the_slope() {
+        // The axis rises by half its run, the top of the union of the spheres along it being
+        // `w sqrt(1 + 1/4)` above the axis, ahead of the point.
+        let w = 0.2;
+        let wire = wire(&[[-1., 0., 0.], [1., 0., 1.]], w);
+        let hit = hit(&wire, 0., 0.).unwrap();
+        assert!(hit.t > 0.5, "{}", hit.t);
+        let expected = 0.5 + w * 1.25_f32.sqrt();
+        assert!((hit.z - expected).abs() < 1e-4, "{} != {expected}", hit.z);
+    }
+
+    #[test]
+    fn overlaps_hit_the_highest_segment() {
+        // The first segment runs along x at 0, the last crosses it along y at 0.5.
+        let wire = wire(
+            &[[-1., 0., 0.], [1., 0., 0.], [0., -1., 0.5], [0., 1., 0.5]],
+            0.2,
+        );
+        let crossing = hit(&wire, 0.05, 0.).unwrap();
+        assert_eq!(crossing.segment, 2);
+        assert!(crossing.z > 0.5);
+        assert_eq!(hit(&wire, 0.5, 0.).unwrap().segment, 0);
     }
 }